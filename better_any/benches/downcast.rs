@@ -0,0 +1,72 @@
+use better_any::{tid, Tid, TidExt};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::rc::Rc;
+
+struct S1(usize);
+tid!(S1);
+
+struct S2<'a>(&'a str);
+tid!(S2<'a>);
+
+fn downcast_ref(c: &mut Criterion) {
+    let s1 = S1(5);
+    let erased = &s1 as &dyn Tid;
+    c.bench_function("downcast_ref hit", |b| {
+        b.iter(|| black_box(erased).downcast_ref::<S1>().map(|it| it.0))
+    });
+    c.bench_function("downcast_ref miss", |b| {
+        b.iter(|| black_box(erased).downcast_ref::<S2>().map(|it| it.0))
+    });
+}
+
+fn downcast_any_ref(c: &mut Criterion) {
+    let value = 5usize;
+    let erased: &dyn Tid = (&value).into();
+    c.bench_function("downcast_any_ref hit", |b| {
+        b.iter(|| black_box(erased).downcast_any_ref::<usize>().copied())
+    });
+    c.bench_function("downcast_any_ref miss", |b| {
+        b.iter(|| black_box(erased).downcast_any_ref::<u32>().copied())
+    });
+}
+
+fn downcast_smart_pointers(c: &mut Criterion) {
+    c.bench_function("downcast_box", |b| {
+        b.iter(|| {
+            let erased = Box::new(S1(5)) as Box<dyn Tid>;
+            black_box(erased).downcast_box::<S1>().ok().map(|it| it.0)
+        })
+    });
+    let rc = Rc::new(S1(5)) as Rc<dyn Tid>;
+    c.bench_function("downcast_rc", |b| {
+        b.iter(|| {
+            black_box(rc.clone())
+                .downcast_rc::<S1>()
+                .ok()
+                .map(|it| it.0)
+        })
+    });
+}
+
+fn downcast_sized(c: &mut Criterion) {
+    fn generic<'a, T: Tid<'a>>(values: &[T]) -> usize {
+        values
+            .iter()
+            .filter_map(|it| it.downcast_ref::<S1>())
+            .map(|it| it.0)
+            .sum()
+    }
+    let values = (0..1000).map(S1).collect::<Vec<_>>();
+    c.bench_function("downcast_ref sized loop", |b| {
+        b.iter(|| generic(black_box(&values)))
+    });
+}
+
+criterion_group!(
+    benches,
+    downcast_ref,
+    downcast_any_ref,
+    downcast_smart_pointers,
+    downcast_sized
+);
+criterion_main!(benches);
@@ -0,0 +1,289 @@
+//! Creates `dyn Tid` objects of different types, either directly or via `Any` bridge,
+//! and checks that downcasts succeed exactly when requested type and creation path match.
+//!
+//! Input is a sequence of operations, each encoded as `op, arg, extra` bytes.
+#![no_main]
+use better_any::{swap_erased, tid, Tid, TidExt};
+use libfuzzer_sys::fuzz_target;
+use std::cell::Cell;
+
+#[derive(PartialEq)]
+struct Empty;
+tid!(Empty);
+
+#[repr(align(64))]
+#[derive(PartialEq)]
+struct Aligned([u8; 3]);
+tid!(Aligned);
+
+#[derive(PartialEq)]
+struct Borrowed<'a>(&'a [u8]);
+tid!(Borrowed<'a>);
+
+/// Tracks number of live values to check that every value is dropped exactly once
+struct Dropper<'a>(&'a Cell<isize>, u8);
+tid!(Dropper<'a>);
+
+impl Drop for Dropper<'_> {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() - 1);
+    }
+}
+
+impl PartialEq for Dropper<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.0, other.0) && self.1 == other.1
+    }
+}
+
+struct Ctx<'a> {
+    data: &'a [u8],
+    live: &'a Cell<isize>,
+}
+
+trait Kind<'a>: Tid<'a> + PartialEq + Sized {
+    fn make(seed: u8, ctx: &Ctx<'a>) -> Self;
+
+    /// Creates `dyn Tid` via `Any` bridge, `None` for non-static types
+    fn bridge(self) -> Option<Box<dyn Tid<'a> + 'a>>;
+
+    fn any_ref<'b>(obj: &'b (dyn Tid<'a> + 'a)) -> Option<&'b Self>;
+
+    fn any_mut<'b>(obj: &'b mut (dyn Tid<'a> + 'a)) -> Option<&'b mut Self>;
+
+    fn any_box(obj: Box<dyn Tid<'a> + 'a>) -> Result<Box<Self>, Box<dyn Tid<'a> + 'a>>;
+}
+
+macro_rules! static_kind {
+    ($ty: ty, |$seed: ident| $make: expr) => {
+        impl<'a> Kind<'a> for $ty {
+            fn make($seed: u8, _: &Ctx<'a>) -> Self {
+                $make
+            }
+
+            fn bridge(self) -> Option<Box<dyn Tid<'a> + 'a>> {
+                Some(Box::new(self).into())
+            }
+
+            fn any_ref<'b>(obj: &'b (dyn Tid<'a> + 'a)) -> Option<&'b Self> {
+                obj.downcast_any_ref()
+            }
+
+            fn any_mut<'b>(obj: &'b mut (dyn Tid<'a> + 'a)) -> Option<&'b mut Self> {
+                obj.downcast_any_mut()
+            }
+
+            fn any_box(obj: Box<dyn Tid<'a> + 'a>) -> Result<Box<Self>, Box<dyn Tid<'a> + 'a>> {
+                obj.downcast_any_box()
+            }
+        }
+    };
+}
+
+macro_rules! borrowed_kind {
+    ($ty: ty, |$seed: ident, $ctx: ident| $make: expr) => {
+        impl<'a> Kind<'a> for $ty {
+            fn make($seed: u8, $ctx: &Ctx<'a>) -> Self {
+                $make
+            }
+
+            fn bridge(self) -> Option<Box<dyn Tid<'a> + 'a>> {
+                None
+            }
+
+            fn any_ref<'b>(_: &'b (dyn Tid<'a> + 'a)) -> Option<&'b Self> {
+                None
+            }
+
+            fn any_mut<'b>(_: &'b mut (dyn Tid<'a> + 'a)) -> Option<&'b mut Self> {
+                None
+            }
+
+            fn any_box(obj: Box<dyn Tid<'a> + 'a>) -> Result<Box<Self>, Box<dyn Tid<'a> + 'a>> {
+                Err(obj)
+            }
+        }
+    };
+}
+
+static_kind!(u8, |seed| seed);
+static_kind!(u64, |seed| seed as u64 * 0x0101_0101_0101_0101);
+static_kind!(Empty, |_seed| Empty);
+static_kind!(Aligned, |seed| Aligned([seed; 3]));
+static_kind!(String, |seed| seed.to_string());
+static_kind!(Vec<u16>, |seed| vec![seed as u16; seed as usize % 8]);
+borrowed_kind!(Borrowed<'a>, |seed, ctx| Borrowed(
+    &ctx.data[..seed as usize % (ctx.data.len() + 1)]
+));
+borrowed_kind!(Dropper<'a>, |seed, ctx| {
+    ctx.live.set(ctx.live.get() + 1);
+    Dropper(ctx.live, seed)
+});
+
+const KINDS: u8 = 8;
+
+/// Calls generic function `$f` with the type corresponding to `$kind`
+macro_rules! dispatch {
+    ($kind: expr, $f: ident($($arg: expr),*)) => {
+        match $kind % KINDS {
+            0 => $f::<u8>($($arg),*),
+            1 => $f::<u64>($($arg),*),
+            2 => $f::<Empty>($($arg),*),
+            3 => $f::<Aligned>($($arg),*),
+            4 => $f::<String>($($arg),*),
+            5 => $f::<Vec<u16>>($($arg),*),
+            6 => $f::<Borrowed<'_>>($($arg),*),
+            _ => $f::<Dropper<'_>>($($arg),*),
+        }
+    };
+}
+
+struct Object<'a> {
+    kind: u8,
+    seed: u8,
+    bridged: bool,
+    value: Box<dyn Tid<'a> + 'a>,
+}
+
+impl Object<'_> {
+    fn matches(&self, kind: u8, via_any: bool) -> bool {
+        self.kind == kind % KINDS && self.bridged == via_any
+    }
+}
+
+fn create<'a, T: Kind<'a>>(ctx: &Ctx<'a>, kind: u8, seed: u8, bridged: bool) -> Object<'a> {
+    let value = T::make(seed, ctx);
+    let (value, bridged) = if bridged {
+        match value.bridge() {
+            Some(value) => (value, true),
+            None => (Box::new(T::make(seed, ctx)) as _, false),
+        }
+    } else {
+        (Box::new(value) as _, false)
+    };
+    Object {
+        kind: kind % KINDS,
+        seed,
+        bridged,
+        value,
+    }
+}
+
+fn check<'a, T: Kind<'a>>(ctx: &Ctx<'a>, obj: &T, seed: u8) {
+    assert_eq!(obj as *const T as usize % std::mem::align_of::<T>(), 0);
+    assert!(*obj == T::make(seed, ctx));
+}
+
+fn downcast_ref<'a, T: Kind<'a>>(ctx: &Ctx<'a>, obj: &Object<'a>, via_any: bool) -> bool {
+    let result = if via_any {
+        T::any_ref(&*obj.value)
+    } else {
+        (*obj.value).downcast_ref::<T>()
+    };
+    if let Some(value) = result {
+        check(ctx, value, obj.seed);
+    }
+    result.is_some()
+}
+
+fn downcast_mut<'a, T: Kind<'a>>(ctx: &Ctx<'a>, obj: &mut Object<'a>, via_any: bool) -> bool {
+    let seed = obj.seed;
+    let result = if via_any {
+        T::any_mut(&mut *obj.value)
+    } else {
+        (*obj.value).downcast_mut::<T>()
+    };
+    match result {
+        Some(value) => {
+            check(ctx, value, seed);
+            true
+        }
+        None => false,
+    }
+}
+
+fn downcast_box<'a, T: Kind<'a>>(
+    ctx: &Ctx<'a>,
+    obj: Object<'a>,
+    via_any: bool,
+) -> Result<(), Object<'a>> {
+    let Object {
+        kind,
+        seed,
+        bridged,
+        value,
+    } = obj;
+    let result = if via_any {
+        T::any_box(value)
+    } else {
+        value.downcast_box::<T>()
+    };
+    match result {
+        Ok(value) => {
+            check(ctx, &*value, seed);
+            Ok(())
+        }
+        Err(value) => Err(Object {
+            kind,
+            seed,
+            bridged,
+            value,
+        }),
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let live = Cell::new(0);
+    {
+        let ctx = Ctx { data, live: &live };
+        let mut objects: Vec<Object<'_>> = Vec::new();
+        for chunk in data.chunks_exact(3) {
+            let (op, arg, extra) = (chunk[0], chunk[1], chunk[2]);
+            let flag = op & 0x80 != 0;
+            let op = op % 5;
+            if op == 0 || objects.is_empty() {
+                objects.push(dispatch!(arg, create(&ctx, arg, extra, flag)));
+                continue;
+            }
+            let index = arg as usize % objects.len();
+            match op {
+                1 => {
+                    let obj = &objects[index];
+                    let expected = obj.matches(extra, flag);
+                    assert_eq!(dispatch!(extra, downcast_ref(&ctx, obj, flag)), expected);
+                }
+                2 => {
+                    let obj = &mut objects[index];
+                    let expected = obj.matches(extra, flag);
+                    assert_eq!(dispatch!(extra, downcast_mut(&ctx, obj, flag)), expected);
+                }
+                3 => {
+                    let obj = objects.swap_remove(index);
+                    let expected = obj.matches(extra, flag);
+                    match dispatch!(extra, downcast_box(&ctx, obj, flag)) {
+                        Ok(()) => assert!(expected),
+                        Err(obj) => {
+                            assert!(!expected);
+                            objects.push(obj);
+                        }
+                    }
+                }
+                _ => {
+                    let other = extra as usize % objects.len();
+                    if index == other {
+                        continue;
+                    }
+                    let (a, b) = if index < other { (index, other) } else { (other, index) };
+                    let (left, right) = objects.split_at_mut(b);
+                    let (a, b) = (&mut left[a], &mut right[0]);
+                    let same = a.kind == b.kind && a.bridged == b.bridged;
+                    assert_eq!(swap_erased(&mut *a.value, &mut *b.value).is_ok(), same);
+                    if same {
+                        std::mem::swap(&mut a.seed, &mut b.seed);
+                    }
+                }
+            }
+        }
+    }
+    assert_eq!(live.get(), 0);
+});
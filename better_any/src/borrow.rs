@@ -0,0 +1,48 @@
+use crate::{Tid, TidExt};
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Kind of borrow, either [`Shared`] or [`Exclusive`].
+///
+/// Allows generic code to be generic over mutability, see [`TidExt::downcast_borrow`].
+pub trait BorrowKind: sealed::Sealed {
+    /// Reference of this kind
+    type Ref<'b, T: ?Sized + 'b>;
+
+    /// Implementation detail
+    #[doc(hidden)]
+    fn __downcast<'a: 'b, 'b, X: ?Sized + Tid<'a>, T: Tid<'a>>(
+        value: Self::Ref<'b, X>,
+    ) -> Option<Self::Ref<'b, T>>;
+}
+
+/// Shared borrow, `&T`
+pub struct Shared;
+
+/// Exclusive borrow, `&mut T`
+pub struct Exclusive;
+
+impl sealed::Sealed for Shared {}
+impl sealed::Sealed for Exclusive {}
+
+impl BorrowKind for Shared {
+    type Ref<'b, T: ?Sized + 'b> = &'b T;
+
+    #[inline]
+    fn __downcast<'a: 'b, 'b, X: ?Sized + Tid<'a>, T: Tid<'a>>(value: &'b X) -> Option<&'b T> {
+        value.downcast_ref()
+    }
+}
+
+impl BorrowKind for Exclusive {
+    type Ref<'b, T: ?Sized + 'b> = &'b mut T;
+
+    #[inline]
+    fn __downcast<'a: 'b, 'b, X: ?Sized + Tid<'a>, T: Tid<'a>>(
+        value: &'b mut X,
+    ) -> Option<&'b mut T> {
+        value.downcast_mut()
+    }
+}
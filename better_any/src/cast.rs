@@ -0,0 +1,68 @@
+use crate::Tid;
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::sync::Arc;
+use core::ops::Deref;
+
+/// Smart pointers that can be reinterpreted as smart pointer `To` of the same kind.
+///
+/// Stable counterpart of `nightly::IntoRawPtr`, limited to pointers directly to the value.
+///
+/// # Safety
+///
+/// Implementation must only change type of the pointee, keeping data pointer and ownership the same.
+pub unsafe trait Cast<To>: Deref {
+    /// Reinterprets pointer to `Self::Target` as a pointer `To`
+    ///
+    /// # Safety
+    ///
+    /// Value behind the pointer must be of the type `To` points to
+    unsafe fn cast(self) -> To;
+}
+
+unsafe impl<X: ?Sized, T> Cast<Box<T>> for Box<X> {
+    unsafe fn cast(self) -> Box<T> {
+        Box::from_raw(Box::into_raw(self) as *mut T)
+    }
+}
+
+unsafe impl<X: ?Sized, T> Cast<Rc<T>> for Rc<X> {
+    unsafe fn cast(self) -> Rc<T> {
+        Rc::from_raw(Rc::into_raw(self) as *const T)
+    }
+}
+
+unsafe impl<X: ?Sized, T> Cast<Arc<T>> for Arc<X> {
+    unsafe fn cast(self) -> Arc<T> {
+        Arc::from_raw(Arc::into_raw(self) as *const T)
+    }
+}
+
+/// Downcasts smart pointer to trait object with `Tid` bound to the same kind of smart pointer to concrete type.
+///
+/// Works on stable, but unlike `nightly::downcast_tid` only for `Box`, `Rc` or `Arc` directly to trait object.
+///
+/// ```rust
+/// # use better_any::{tid, Tid};
+/// # use better_any::cast::downcast_smart;
+/// # use std::rc::Rc;
+/// struct Test(i32);
+/// tid!(Test);
+/// let rc = Rc::new(Test(5)) as Rc<dyn Tid>;
+/// let result: Rc<Test> = downcast_smart(rc).unwrap_or_else(|_| panic!("error"));
+/// assert_eq!(5, result.0);
+/// ```
+pub fn downcast_smart<'a, F, T>(f: F) -> Result<T, F>
+where
+    F: Cast<T>,
+    F::Target: Tid<'a>,
+    T: Deref,
+    T::Target: Tid<'a> + Sized,
+{
+    if f.self_id() == <T::Target as Tid<'a>>::id() {
+        // SAFETY: type ids are the same so value behind `f` has type `T::Target`
+        Ok(unsafe { f.cast() })
+    } else {
+        Err(f)
+    }
+}
@@ -0,0 +1,70 @@
+use crate::{Tid, TidExt};
+use core::cell::{BorrowError, Ref, RefCell, RefMut};
+use core::fmt;
+
+/// Error returned by [`RefCellExt::try_borrow_downcast`]
+#[derive(Debug)]
+pub enum BorrowOrTypeError {
+    /// Value is currently mutably borrowed
+    Borrow(BorrowError),
+    /// Value is not of requested type
+    TypeMismatch,
+}
+
+impl fmt::Display for BorrowOrTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BorrowOrTypeError::Borrow(err) => fmt::Display::fmt(err, f),
+            BorrowOrTypeError::TypeMismatch => f.write_str("value is not of requested type"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BorrowOrTypeError {}
+
+/// Extension trait to borrow and downcast value inside of `RefCell` in one step
+///
+/// ```rust
+/// # use better_any::{tid, Tid};
+/// # use better_any::cell::{BorrowOrTypeError, RefCellExt};
+/// # use std::cell::RefCell;
+/// struct S(usize);
+/// tid!(S);
+///
+/// let cell: Box<RefCell<dyn Tid>> = Box::new(RefCell::new(S(5)));
+/// assert_eq!(cell.try_borrow_downcast::<S>().unwrap().0, 5);
+/// assert!(matches!(cell.try_borrow_downcast::<usize>(), Err(BorrowOrTypeError::TypeMismatch)));
+/// ```
+pub trait RefCellExt<'a> {
+    /// Immutably borrows the cell and downcasts its value to `T`
+    fn try_borrow_downcast<T: Tid<'a>>(&self) -> Result<Ref<'_, T>, BorrowOrTypeError>;
+}
+
+impl<'a, X: ?Sized + Tid<'a>> RefCellExt<'a> for RefCell<X> {
+    fn try_borrow_downcast<T: Tid<'a>>(&self) -> Result<Ref<'_, T>, BorrowOrTypeError> {
+        let borrowed = self.try_borrow().map_err(BorrowOrTypeError::Borrow)?;
+        Ref::filter_map(borrowed, |it| it.downcast_ref::<T>())
+            .map_err(|_| BorrowOrTypeError::TypeMismatch)
+    }
+}
+
+/// Downcasts mutable borrow of the value inside of `RefCell`, returning original guard on type mismatch
+///
+/// ```rust
+/// # use better_any::{tid, Tid, TidExt};
+/// # use better_any::cell::downcast_ref_mut_guard;
+/// # use std::cell::RefCell;
+/// struct S(usize);
+/// tid!(S);
+///
+/// let cell: Box<RefCell<dyn Tid>> = Box::new(RefCell::new(S(5)));
+/// let guard = downcast_ref_mut_guard::<usize, _>(cell.borrow_mut()).err().unwrap();
+/// downcast_ref_mut_guard::<S, _>(guard).ok().unwrap().0 += 1;
+/// assert_eq!(cell.borrow().downcast_ref::<S>().unwrap().0, 6);
+/// ```
+pub fn downcast_ref_mut_guard<'b, 'a, T: Tid<'a>, X: ?Sized + Tid<'a>>(
+    r: RefMut<'b, X>,
+) -> Result<RefMut<'b, T>, RefMut<'b, X>> {
+    RefMut::filter_map(r, |it| it.downcast_mut::<T>())
+}
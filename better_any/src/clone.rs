@@ -0,0 +1,35 @@
+use crate::Tid;
+use alloc::boxed::Box;
+
+/// `Tid` types that can be cloned after type erasure
+///
+/// Implemented for every `Tid` type that is `Clone`, so it is enough to use `dyn CloneTid`
+/// instead of `dyn Tid` where cloning is needed. `Tid` itself stays free of `Clone` requirement.
+///
+/// ```rust
+/// # use better_any::{tid, TidExt};
+/// # use better_any::clone::CloneTid;
+/// #[derive(Clone)]
+/// struct S(usize);
+/// tid!(S);
+///
+/// let prototype: Box<dyn CloneTid> = Box::new(S(5));
+/// let copy = prototype.clone();
+/// assert_eq!(copy.downcast_ref::<S>().unwrap().0, 5);
+/// ```
+pub trait CloneTid<'a>: Tid<'a> {
+    /// Clones `self` into a new box
+    fn clone_box(&self) -> Box<dyn CloneTid<'a> + 'a>;
+}
+
+impl<'a, T: Tid<'a> + Clone> CloneTid<'a> for T {
+    fn clone_box(&self) -> Box<dyn CloneTid<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
+
+impl<'a> Clone for Box<dyn CloneTid<'a> + 'a> {
+    fn clone(&self) -> Self {
+        (**self).clone_box()
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{Tid, TidExt};
+use alloc::boxed::Box;
+use core::any::TypeId;
+use core::cell::RefCell;
+use core::marker::PhantomData;
+use std::collections::HashMap;
+
+/// Cache of values resolved for particular objects, keyed by object address and its type id
+///
+/// Useful to memoize dispatch on the same objects.
+/// Note that if object is dropped and another object of the same type is allocated at the same address,
+/// cached value of the old object is returned for it, so call `clear` when objects can be reallocated.
+/// Values for zero-sized objects are never cached, since distinct objects of the same zero-sized type
+/// can share the same address.
+///
+/// ```rust
+/// # use better_any::{Tid, TidExt};
+/// # use better_any::dispatch::DispatchCache;
+/// let cache = DispatchCache::new();
+/// let value = 5usize;
+/// let handler = cache.resolve(&value, |it| it.is::<usize>());
+/// assert!(handler);
+/// // already cached
+/// assert!(cache.resolve(&value, |_| unreachable!()));
+/// ```
+pub struct DispatchCache<'a, H> {
+    cache: RefCell<HashMap<(usize, TypeId), H>>,
+    // same variance as `dyn Tid<'a>`
+    _lifetime: PhantomData<fn(&'a ()) -> &'a ()>,
+}
+
+impl<'a, H> Default for DispatchCache<'a, H> {
+    fn default() -> Self {
+        DispatchCache {
+            cache: RefCell::new(HashMap::new()),
+            _lifetime: PhantomData,
+        }
+    }
+}
+
+impl<'a, H: Clone> DispatchCache<'a, H> {
+    /// Creates empty cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns cached value for `object` or calls `build` and caches its result if there is none.
+    ///
+    /// `build` can call `resolve` on the same cache.
+    /// If it resolves the same `object` recursively, value returned by the outermost `build` is cached.
+    pub fn resolve(
+        &self,
+        object: &(dyn Tid<'a> + 'a),
+        build: impl FnOnce(&(dyn Tid<'a> + 'a)) -> H,
+    ) -> H {
+        if core::mem::size_of_val(object) == 0 {
+            return build(object);
+        }
+        let key = (object as *const _ as *const () as usize, object.self_id());
+        if let Some(value) = self.cache.borrow().get(&key) {
+            return value.clone();
+        }
+        let value = build(object);
+        self.cache.borrow_mut().insert(key, value.clone());
+        value
+    }
+
+    /// Number of cached values
+    pub fn len(&self) -> usize {
+        self.cache.borrow().len()
+    }
+
+    /// Returns `true` if there are no cached values
+    pub fn is_empty(&self) -> bool {
+        self.cache.borrow().is_empty()
+    }
+
+    /// Removes all cached values
+    pub fn clear(&self) {
+        self.cache.borrow_mut().clear()
+    }
+}
+
+type Handler<'a, R> = Box<dyn Fn(&(dyn Tid<'a> + 'a)) -> R + 'a>;
+
+/// Dispatcher that calls handler registered for the type of the object
+/// or default handler if there is none
+///
+/// Intended for open sets of types like plugin registries, where unknown types are expected.
+/// For a closed set of types see `tid_closed_set` attribute.
+///
+/// ```rust
+/// # use better_any::{Tid, TidExt};
+/// # use better_any::dispatch::TidDispatcher;
+/// let mut dispatcher = TidDispatcher::new(|it| format!("unknown {}", it.type_name()));
+/// dispatcher.register(|it: &u32| format!("u32 {}", it));
+/// dispatcher.register(|it: &&str| format!("str {}", it));
+///
+/// assert_eq!(dispatcher.dispatch(&5u32), "u32 5");
+/// assert_eq!(dispatcher.dispatch(&"x"), "str x");
+/// assert_eq!(dispatcher.dispatch(&5u8), "unknown u8");
+/// ```
+pub struct TidDispatcher<'a, R> {
+    handlers: HashMap<TypeId, Handler<'a, R>>,
+    default: Handler<'a, R>,
+}
+
+impl<'a, R> TidDispatcher<'a, R> {
+    /// Creates dispatcher that calls `default` for all objects
+    pub fn new(default: impl Fn(&(dyn Tid<'a> + 'a)) -> R + 'a) -> Self {
+        TidDispatcher {
+            handlers: HashMap::new(),
+            default: Box::new(default),
+        }
+    }
+
+    /// Registers `handler` for objects of type `T`, replacing previously registered one if any
+    pub fn register<T: Tid<'a>>(&mut self, handler: impl Fn(&T) -> R + 'a) {
+        self.handlers.insert(
+            T::id(),
+            Box::new(move |object| handler(object.downcast_ref::<T>().unwrap())),
+        );
+    }
+
+    /// Returns `true` if there is a handler registered for type of `object`
+    pub fn is_registered(&self, object: &(dyn Tid<'a> + 'a)) -> bool {
+        self.handlers.contains_key(&object.self_id())
+    }
+
+    /// Calls handler registered for type of `object` or default handler if there is none
+    pub fn dispatch(&self, object: &(dyn Tid<'a> + 'a)) -> R {
+        match self.handlers.get(&object.self_id()) {
+            Some(handler) => handler(object),
+            None => (self.default)(object),
+        }
+    }
+}
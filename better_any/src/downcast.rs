@@ -0,0 +1,127 @@
+use crate::Tid;
+use alloc::boxed::Box;
+
+/// Analog of `downcast_rs::Downcast` for `Tid` types
+///
+/// Use it as a supertrait of your trait together with `impl_downcast_tid!`.
+/// Migration from `downcast-rs` maps as follows:
+///
+/// | `downcast-rs`                 | `better_any`                          |
+/// |-------------------------------|---------------------------------------|
+/// | `trait Base: Downcast`        | `trait Base<'a>: DowncastTid<'a>`     |
+/// | `impl_downcast!(Base)`        | `impl_downcast_tid!(Base<'a>)`        |
+/// | `as_any`, `as_any_mut`        | `as_tid`, `as_tid_mut`                |
+/// | `into_any`                    | `into_tid`                            |
+/// | `is`, `downcast_ref`, `downcast_mut`, `downcast` | same                |
+///
+/// Traits without lifetime parameter can use `DowncastTid<'static>`
+/// with `impl_downcast_tid!(Base)`. Generic traits and `sync` form are not supported.
+pub trait DowncastTid<'a>: Tid<'a> {
+    /// Upcasts boxed value to `Box<dyn Tid>`
+    fn into_tid(self: Box<Self>) -> Box<dyn Tid<'a> + 'a>;
+    /// Upcasts reference to `&dyn Tid`
+    fn as_tid(&self) -> &(dyn Tid<'a> + 'a);
+    /// Upcasts mutable reference to `&mut dyn Tid`
+    fn as_tid_mut(&mut self) -> &mut (dyn Tid<'a> + 'a);
+}
+
+impl<'a, T: Tid<'a>> DowncastTid<'a> for T {
+    fn into_tid(self: Box<Self>) -> Box<dyn Tid<'a> + 'a> {
+        self
+    }
+
+    fn as_tid(&self) -> &(dyn Tid<'a> + 'a) {
+        self
+    }
+
+    fn as_tid_mut(&mut self) -> &mut (dyn Tid<'a> + 'a) {
+        self
+    }
+}
+
+/// Adds `downcast-rs` style downcasting methods to trait objects of a trait with `DowncastTid` supertrait
+///
+/// ```rust
+/// # use better_any::{impl_downcast_tid, tid, Tid};
+/// # use better_any::downcast::DowncastTid;
+/// trait Shape<'a>: DowncastTid<'a> {
+///     fn name(&self) -> &'a str;
+/// }
+/// impl_downcast_tid!(Shape<'a>);
+///
+/// struct Circle<'a>(&'a str);
+/// tid!(Circle<'a>);
+/// impl<'a> Shape<'a> for Circle<'a> {
+///     fn name(&self) -> &'a str {
+///         self.0
+///     }
+/// }
+///
+/// let shape: Box<dyn Shape> = Box::new(Circle("circle"));
+/// assert!(shape.is::<Circle>());
+/// assert_eq!(shape.downcast_ref::<Circle>().unwrap().0, "circle");
+/// assert_eq!(shape.downcast::<Circle>().ok().unwrap().name(), "circle");
+/// ```
+#[macro_export]
+macro_rules! impl_downcast_tid {
+    ($trait: ident < $lt: lifetime >) => {
+        $crate::impl_downcast_tid! { @impl [$lt] $lt; dyn $trait<$lt> + $lt }
+    };
+    ($trait: ident) => {
+        $crate::impl_downcast_tid! { @impl [] 'static; dyn $trait }
+    };
+    (@impl [$($generic: lifetime)?] $lt: lifetime; $($ty: tt)+) => {
+        #[allow(dead_code)]
+        impl<$($generic)?> $($ty)+ {
+            /// Returns true if the type behind trait object is `T`
+            #[inline]
+            pub fn is<T: $crate::Tid<$lt>>(&self) -> bool {
+                $crate::TidExt::is::<T>(self)
+            }
+
+            /// Attempts to downcast trait object to `T` behind reference
+            #[inline]
+            pub fn downcast_ref<T: $crate::Tid<$lt>>(&self) -> ::core::option::Option<&T> {
+                $crate::TidExt::downcast_ref::<T>(self)
+            }
+
+            /// Attempts to downcast trait object to `T` behind mutable reference
+            #[inline]
+            pub fn downcast_mut<T: $crate::Tid<$lt>>(&mut self) -> ::core::option::Option<&mut T> {
+                $crate::TidExt::downcast_mut::<T>(self)
+            }
+
+            /// Attempts to downcast boxed trait object to `T`
+            #[inline]
+            pub fn downcast<T: $crate::Tid<$lt>>(
+                self: $crate::__Box<Self>,
+            ) -> ::core::result::Result<$crate::__Box<T>, $crate::__Box<Self>> {
+                $crate::TidExt::downcast_box::<T>(self)
+            }
+        }
+    };
+}
+
+/// Converts object implementing `downcast_rs::Downcast` to `dyn Tid`
+///
+/// It uses `From<&dyn Any>` implementation, so only `'static` types are supported
+/// and original value can be recovered only via `<dyn Tid>::downcast_any_ref`.
+/// `From` implementation can't return reference to `dyn Tid`, so result is boxed.
+///
+/// ```rust
+/// # use better_any::downcast::from_downcast;
+/// # use downcast_rs::{impl_downcast, Downcast};
+/// trait Plugin: Downcast {}
+/// impl_downcast!(Plugin);
+///
+/// struct Logger(usize);
+/// impl Plugin for Logger {}
+///
+/// let plugin: Box<dyn Plugin> = Box::new(Logger(5));
+/// let value = from_downcast(&*plugin);
+/// assert_eq!(value.downcast_any_ref::<Logger>().unwrap().0, 5);
+/// ```
+#[cfg(feature = "downcast-rs")]
+pub fn from_downcast<'a, D: ?Sized + downcast_rs::Downcast>(d: &'a D) -> Box<dyn Tid<'a> + 'a> {
+    d.as_any().into()
+}
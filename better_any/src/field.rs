@@ -0,0 +1,74 @@
+use crate::Tid;
+use core::fmt;
+
+/// `Tid` types that also implement `Debug`, so their values can be shown after type erasure
+pub trait DebugTid<'a>: Tid<'a> + fmt::Debug {}
+
+impl<'a, T: ?Sized + Tid<'a> + fmt::Debug> DebugTid<'a> for T {}
+
+/// Wrapper to show type erased value in logs.
+///
+/// `Debug` implementation shows name of the concrete type and, if created via `with_debug`, its value.
+/// To record it as a `tracing` field use `?` sigil like `tracing::info!(value = ?field)`
+/// or `value` method with `tracing` feature.
+///
+/// ```rust
+/// # use better_any::{tid, Tid};
+/// # use better_any::field::TidField;
+/// #[derive(Debug)]
+/// struct S(usize);
+/// tid!(S);
+///
+/// let value = &S(5) as &dyn Tid;
+/// assert!(format!("{:?}", TidField::new(value)).ends_with("S"));
+/// assert!(format!("{:?}", TidField::with_debug(&S(5))).ends_with("S: S(5)"));
+/// ```
+#[derive(Clone, Copy)]
+pub struct TidField<'r, 'a> {
+    value: Value<'r, 'a>,
+}
+
+#[derive(Clone, Copy)]
+enum Value<'r, 'a> {
+    Opaque(&'r (dyn Tid<'a> + 'a)),
+    Debug(&'r (dyn DebugTid<'a> + 'a)),
+}
+
+impl<'r, 'a> TidField<'r, 'a> {
+    /// Creates field that shows only type name of `value`
+    pub fn new(value: &'r (dyn Tid<'a> + 'a)) -> Self {
+        TidField {
+            value: Value::Opaque(value),
+        }
+    }
+
+    /// Creates field that shows type name and `Debug` representation of `value`
+    pub fn with_debug(value: &'r (dyn DebugTid<'a> + 'a)) -> Self {
+        TidField {
+            value: Value::Debug(value),
+        }
+    }
+
+    /// Name of the type of wrapped value
+    pub fn type_name(&self) -> &'static str {
+        match self.value {
+            Value::Opaque(value) => value.type_name(),
+            Value::Debug(value) => value.type_name(),
+        }
+    }
+
+    /// Converts to `tracing` field value
+    #[cfg(feature = "tracing")]
+    pub fn value(self) -> tracing::field::DebugValue<Self> {
+        tracing::field::debug(self)
+    }
+}
+
+impl fmt::Debug for TidField<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.value {
+            Value::Opaque(value) => f.write_str(value.type_name()),
+            Value::Debug(value) => write!(f, "{}: {:?}", value.type_name(), value),
+        }
+    }
+}
@@ -2,6 +2,10 @@
 #![warn(rust_2018_idioms)]
 #![cfg_attr(feature = "nightly", feature(coerce_unsized))]
 #![cfg_attr(feature = "nightly", feature(ptr_metadata))]
+#![cfg_attr(feature = "nightly", feature(allocator_api))]
+#![cfg_attr(feature = "nightly", feature(const_type_name))]
+#![cfg_attr(feature = "nightly", feature(layout_for_ptr))]
+#![cfg_attr(not(feature = "std"), no_std)]
 //! # Better Any
 //!
 //! Rust RFC for `non_static_type_id` feature has been reverted.
@@ -18,18 +22,25 @@
 //! any trait object that implements [`Tid`].
 //! So there is no more need to extend your traits with` fn to_any(&self)-> &dyn Any`
 //!
-//! MSRV: `1.41.0-stable` (without nightly feature)
+//! MSRV: `1.70.0-stable` (without nightly feature),
+//! `saturating` feature that implements `TidAble` for `core::num::Saturating` requires `1.74.0-stable`
+//!
+//! ### `no_std`
+//!
+//! Crate supports `no_std` environments with `alloc` if default `std` feature is disabled.
+//! Then implementations for types from `std` only (like `Mutex` or `HashSet`)
+//! and modules that depend on `HashMap` are not available.
 //!
 //! ### Usage
 //!
 //! Basically in places where before you have used `dyn Any` you can use `dyn Tid<'a>`
 //!  - If your type is generic you should derive `Tid` implementation for it with `tid!` macro or `Tid` derive macro.
-//! Then to retrieve back concrete type `<dyn Tid>::downcast_*` methods should be used.
+//!    Then to retrieve back concrete type `<dyn Tid>::downcast_*` methods should be used.
 //!  - If your type is not generic/implements Any you can create `dyn Tid` from it via any of the available `From` implementations.
-//! Then to retrieve back concrete type `<dyn Tid>::downcast_any_*` methods should be used
+//!    Then to retrieve back concrete type `<dyn Tid>::downcast_any_*` methods should be used
 //!  - If your type is not generic and local to your crate you also can derive `Tid` but then you need to be careful
-//! to use methods that corresponds to the way you create `dyn Tid` for that particular type.
-//! Otherwise downcasting will return `None`.
+//!    to use methods that corresponds to the way you create `dyn Tid` for that particular type.
+//!    Otherwise downcasting will return `None`.
 //!
 //! If all your types can implement `Tid` to avoid confusion
 //! recommended way is to use first option even if some types implement `Any`.
@@ -40,7 +51,8 @@
 //!
 //! Unfortunately you can't just use `Tid` everywhere because currently it is impossible
 //! to implement `Tid` for `T:Any` since it would conflict with any other possible `Tid` implementation.
-//! To overcome this limitation there is a `From` impl to go from `Box/&/&mut T where T:Any` to `Box/&/&mut dyn Tid`.
+//! To overcome this limitation there is a `From` impl to go from `Box/&/&mut T where T:Any` to `Box/&/&mut dyn Tid`,
+//! and `<dyn Tid>::from_any_rc/from_any_arc` functions for `Rc/Arc`.
 //!
 //! Nevertheless if you are using `dyn Trait` where `Trait:Tid` all of this wouldn't work,
 //! and you are left with `Tid` only.
@@ -50,7 +62,17 @@
 //! It is safe because created trait object preserves lifetime information,
 //! thus allowing us to safely downcast with proper lifetime.
 //! Otherwise internally it is plain old `Any`.
-use std::any::{Any, TypeId};
+extern crate alloc;
+
+#[doc(hidden)]
+pub use alloc::boxed::Box as __Box;
+
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::sync::Arc;
+use borrow::BorrowKind;
+use core::any::{Any, TypeId};
+use core::pin::Pin;
 
 /// Attribute macro that makes your implementation of `TidAble` safe
 /// Use it when you can't use derive e.g. for trait object.
@@ -61,7 +83,7 @@ use std::any::{Any, TypeId};
 /// #[impl_tid]
 /// impl<'a> TidAble<'a> for Box<dyn Trait<'a> + 'a>{}
 /// ```
-#[deprecated(since = "0.2", note = "use tid! macro instead")]
+#[deprecated(since = "0.2.0", note = "use tid! macro instead")]
 #[cfg(feature = "derive")]
 pub use better_typeid_derive::impl_tid;
 
@@ -74,9 +96,114 @@ pub use better_typeid_derive::impl_tid;
 /// All of its functionality is available via regular `tid!` macro,
 /// so unless you really want looks/readability of derive macro,
 /// there is no need to drag whole proc-macro machinery to your project.
+///
+/// For example `struct Foo<'a, T>(&'a T)` implements `Tid` only for `T: TidAble<'a>`,
+/// so `Foo<'a, String>` is `Tid` but `Foo<'a, NotTid>` is not.
+/// If `T` is supposed to be any `'static` type, declare it as `struct Foo<'a, T: 'static>(&'a T)`.
+/// Errors about unsatisfied `TidAble` bound point to the type parameter that caused it.
+///
+/// Works the same way for structs, enums and unions.
+/// Unions don't need any additional care, type id depends only on the type itself and not on its fields,
+/// and `dyn Tid<'a>` is invariant over `'a` regardless of the variance of the union.
+/// Reading union fields after downcasting is as unsafe as usual.
+///
+/// Only single lifetime parameter is supported.
+/// If your type has several lifetimes but in practice they are always the same,
+/// `#[tid(unify_lifetimes)]` attribute implements `Tid` only for the case when all of them are equal
+/// (like `tid!` does for `impl<'a> TidAble<'a> for Pair<'a, 'a>`).
+/// It is sound because resulting `dyn Tid<'a>` is still invariant over its single lifetime,
+/// and `Pair<'a, 'b>` with different lifetimes just doesn't implement `Tid`.
+///
+/// ```rust
+/// # use better_any::{Tid, TidAble, TidExt};
+/// #[derive(Tid)]
+/// #[tid(unify_lifetimes)]
+/// struct Pair<'a, 'b>(&'a str, &'b str);
+///
+/// let (x, y) = (String::from("x"), String::from("y"));
+/// let pair = Pair(&x, &y);
+/// assert_eq!((&pair as &dyn Tid).downcast_ref::<Pair>().unwrap().1, "y");
+/// ```
+///
+/// ```rust,compile_fail
+/// # use better_any::{Tid, TidAble};
+/// #[derive(Tid)]
+/// struct Pair<'a, 'b>(&'a str, &'b str);
+/// ```
 #[cfg(feature = "derive")]
 pub use better_typeid_derive::Tid;
 
+/// Derive macro to implement `TidTransparent` for `#[repr(transparent)]` structs with single field
+///
+/// ```rust
+/// # use better_any::{Tid, TidAble, TidExt, TidTransparent};
+/// #[derive(Tid, TidTransparent)]
+/// #[repr(transparent)]
+/// struct Meters(f64);
+///
+/// let value = &Meters(5.0) as &dyn Tid;
+/// assert_eq!(value.downcast_transparent_ref::<Meters>(), Some(&5.0));
+/// ```
+///
+/// ```rust,compile_fail
+/// # use better_any::{Tid, TidAble, TidTransparent};
+/// #[derive(Tid, TidTransparent)]
+/// struct Meters(f64);
+/// ```
+#[cfg(feature = "derive")]
+pub use better_typeid_derive::TidTransparent;
+
+/// Attribute macro for an enum that lists a closed set of types, one per variant
+///
+/// Generates `erase` method that puts value of the variant into `Box<dyn Tid>`
+/// and `downcast` function that recovers the variant from the type of erased value.
+/// Since `downcast` returns the enum, every `match` on it has to handle all types of the set,
+/// so adding a type to the set forces updating all of them.
+/// Each variant must have single unnamed field, types of variants must be different.
+///
+/// ```rust
+/// # use better_any::tid_closed_set;
+/// #[tid_closed_set]
+/// enum Value<'a> {
+///     Int(i32),
+///     Str(&'a str),
+/// }
+///
+/// let erased = Value::Str("x").erase();
+/// let len = match Value::downcast(erased).ok().unwrap() {
+///     Value::Int(_) => 0,
+///     Value::Str(s) => s.len(),
+/// };
+/// assert_eq!(len, 1);
+/// assert!(Value::downcast(Box::new(1u8)).is_err());
+/// ```
+///
+/// ```rust,compile_fail
+/// # use better_any::tid_closed_set;
+/// #[tid_closed_set]
+/// enum Value<'a> {
+///     Int(i32),
+///     Str(&'a str),
+/// }
+///
+/// match Value::downcast(Box::new(5)).ok().unwrap() {
+///     Value::Int(_) => {}
+/// }
+/// ```
+///
+/// Same type in two variants is an error because `downcast` would never return the second one.
+///
+/// ```rust,compile_fail
+/// # use better_any::tid_closed_set;
+/// #[tid_closed_set]
+/// enum Value {
+///     Id(u32),
+///     Count(u32),
+/// }
+/// ```
+#[cfg(feature = "derive")]
+pub use better_typeid_derive::tid_closed_set;
+
 /// This trait indicates that you can substitute this type as a type parameter to
 /// another type so that resulting type could implement `Tid`.
 ///
@@ -91,6 +218,8 @@ pub use better_typeid_derive::Tid;
 /// but you can't make a trait object from it without specifying internal associate type
 /// like: `dyn TidAble<'a,Static=SomeType>` which make such trait object effectively useless.
 ///
+/// # Safety
+///
 /// Unsafe because safety of this crate relies on correctness of this trait implementation.
 /// There are several safe ways to implement it:
 ///  - `type_id`/`tid` declarative macro
@@ -105,6 +234,12 @@ pub unsafe trait TidAble<'a>: Tid<'a> {
     /// Implementation detail
     #[doc(hidden)]
     type Static: ?Sized + Any;
+
+    /// Implementation detail
+    #[doc(hidden)]
+    fn __type_name(&self) -> &'static str {
+        core::any::type_name::<Self>()
+    }
 }
 
 /// Extension trait that contains actual downcasting methods.
@@ -115,6 +250,41 @@ pub unsafe trait TidAble<'a>: Tid<'a> {
 /// Useful if you have generic code that you want to behave differently depending on which
 /// concrete type replaces type parameter. Usually there are better ways to do this like specialization,
 /// but sometimes it can be the only way.
+///
+/// Methods are available for `dyn Tid<'a> + 'b` as well, not only for `dyn Tid<'a> + 'a`.
+/// Trailing `+ 'b` only limits how long the object itself can be used and is covariant,
+/// while `'a` is a part of the type, so `Tid<'a>` is invariant over it
+/// and downcasting only returns types with exactly the same `'a`.
+/// Note that because of the `Tid<'a>: 'a` bound `dyn Tid<'a> + 'b` is well-formed only if `'b: 'a`,
+/// so in fact `'b` can't be shorter than `'a`. For shared references it doesn't matter
+/// because `&'b (dyn Tid<'a> + 'a)` still can be short, but `&'b mut (dyn Tid<'a> + 'a)`
+/// has to borrow the value for the whole `'a`.
+///
+/// ```rust
+/// # use better_any::{tid, Tid, TidExt};
+/// struct S<'a>(&'a str);
+/// tid!(S<'a>);
+///
+/// fn get<'b, 'a>(value: &'b (dyn Tid<'a> + 'b)) -> Option<&'b S<'a>> {
+///     value.downcast_ref()
+/// }
+///
+/// let s = String::from("long");
+/// let value = S(&s);
+/// assert_eq!(get(&value).unwrap().0, "long");
+/// ```
+///
+/// But `'a` can't be laundered into a different lifetime:
+///
+/// ```rust,compile_fail
+/// # use better_any::{tid, Tid, TidExt};
+/// struct S<'a>(&'a str);
+/// tid!(S<'a>);
+///
+/// fn launder<'b, 'a>(value: &'b (dyn Tid<'a> + 'b)) -> Option<&'b S<'static>> {
+///     value.downcast_ref()
+/// }
+/// ```
 pub trait TidExt<'a>: Tid<'a> {
     /// Returns true if type behind self is equal to the type of T.
     fn is<T: Tid<'a>>(&self) -> bool {
@@ -142,6 +312,54 @@ pub trait TidExt<'a>: Tid<'a> {
         }
     }
 
+    /// Attempts to downcast self to `T` behind reference, returns `default` if types don't match
+    fn downcast_ref_or<'b, T: Tid<'a>>(&'b self, default: &'b T) -> &'b T {
+        self.downcast_ref().unwrap_or(default)
+    }
+
+    /// Borrows self as `T` if types match, otherwise returns owned value created by `default`
+    fn downcast_cow<'b, T: Tid<'a> + Clone>(&'b self, default: impl FnOnce() -> T) -> Cow<'b, T> {
+        match self.downcast_ref() {
+            Some(value) => Cow::Borrowed(value),
+            None => Cow::Owned(default()),
+        }
+    }
+
+    /// Same as `downcast_ref` but also returns type id that matched, which is always `T::id()`
+    ///
+    /// Useful for logging.
+    fn downcast_ref_logged<'b, T: Tid<'a>>(&'b self) -> Option<(&'b T, TypeId)> {
+        self.downcast_ref().map(|it| (it, T::id()))
+    }
+
+    /// Attempts to downcast `this` to `T` behind reference of kind `B`
+    ///
+    /// Same as `downcast_ref` or `downcast_mut` depending on `B`,
+    /// useful in code that is generic over mutability.
+    ///
+    /// ```rust
+    /// # use better_any::{tid, Tid, TidExt};
+    /// # use better_any::borrow::{BorrowKind, Exclusive, Shared};
+    /// struct S(usize);
+    /// tid!(S);
+    ///
+    /// fn get<'b, B: BorrowKind>(value: B::Ref<'b, dyn Tid<'static>>) -> Option<B::Ref<'b, S>> {
+    ///     <dyn Tid>::downcast_borrow::<B, S>(value)
+    /// }
+    ///
+    /// let mut value = S(5);
+    /// get::<Exclusive>(&mut value).unwrap().0 += 1;
+    /// assert_eq!(get::<Shared>(&value).unwrap().0, 6);
+    /// ```
+    fn downcast_borrow<'b, B: BorrowKind, T: Tid<'a>>(
+        this: B::Ref<'b, Self>,
+    ) -> Option<B::Ref<'b, T>>
+    where
+        'a: 'b,
+    {
+        B::__downcast(this)
+    }
+
     /// Attempts to downcast self to `T` behind `Rc` pointer
     fn downcast_rc<T: Tid<'a>>(self: Rc<Self>) -> Result<Rc<T>, Rc<Self>> {
         if self.is::<T>() {
@@ -160,6 +378,75 @@ pub trait TidExt<'a>: Tid<'a> {
         }
     }
 
+    /// Checks whether value behind `this` is of type `T` without touching the pointer itself
+    ///
+    /// Together with `downcast_arc_unchecked` it allows to split downcast into check and conversion,
+    /// when most checks are expected to fail.
+    ///
+    /// ```rust
+    /// # use better_any::{Tid, TidExt};
+    /// # use std::sync::Arc;
+    /// let shared: Arc<dyn Tid> = Arc::new(5usize);
+    /// assert!(!TidExt::downcast_arc_peek::<u8>(&shared));
+    /// if TidExt::downcast_arc_peek::<usize>(&shared) {
+    ///     // SAFETY: type was checked above
+    ///     let typed = unsafe { shared.clone().downcast_arc_unchecked::<usize>() };
+    ///     assert_eq!(*typed, 5);
+    /// }
+    /// ```
+    fn downcast_arc_peek<T: Tid<'a>>(this: &Arc<Self>) -> bool {
+        (**this).is::<T>()
+    }
+
+    /// Converts `Arc<Self>` to `Arc<T>` without checking type
+    ///
+    /// # Safety
+    ///
+    /// Value behind `self` must be of type `T`, check it with `downcast_arc_peek` first.
+    unsafe fn downcast_arc_unchecked<T: Tid<'a>>(self: Arc<Self>) -> Arc<T> {
+        debug_assert!(self.is::<T>());
+        Arc::from_raw(Arc::into_raw(self) as *const _)
+    }
+
+    /// Attempts to downcast `this` to `T` behind `rc::Weak` pointer
+    ///
+    /// Type id can be retrieved only while value is still alive,
+    /// so if there are no strong references left, it returns `Err`.
+    ///
+    /// It is an associated function because `Weak` can't be used as a method receiver on stable.
+    fn downcast_weak_rc<T: Tid<'a>>(
+        this: alloc::rc::Weak<Self>,
+    ) -> Result<alloc::rc::Weak<T>, alloc::rc::Weak<Self>> {
+        if this.upgrade().is_some_and(|it| it.is::<T>()) {
+            unsafe {
+                Ok(alloc::rc::Weak::from_raw(
+                    alloc::rc::Weak::into_raw(this) as *const _
+                ))
+            }
+        } else {
+            Err(this)
+        }
+    }
+
+    /// Attempts to downcast `this` to `T` behind `sync::Weak` pointer
+    ///
+    /// See `downcast_weak_rc`
+    fn downcast_weak_arc<T: Tid<'a>>(
+        this: alloc::sync::Weak<Self>,
+    ) -> Result<alloc::sync::Weak<T>, alloc::sync::Weak<Self>> {
+        // even if other strong references are dropped concurrently,
+        // allocation itself is kept alive by `this`
+        if this.upgrade().is_some_and(|it| it.is::<T>()) {
+            unsafe {
+                Ok(alloc::sync::Weak::from_raw(
+                    alloc::sync::Weak::into_raw(this) as *const _,
+                ))
+            }
+        } else {
+            Err(this)
+        }
+    }
+
     /// Attempts to downcast self to `T` behind `Box` pointer
     fn downcast_box<T: Tid<'a>>(self: Box<Self>) -> Result<Box<T>, Box<Self>> {
         if self.is::<T>() {
@@ -169,22 +456,278 @@ pub trait TidExt<'a>: Tid<'a> {
         }
     }
 
+    /// Downcasts self to `T` behind `Box` pointer, or converts it with `f` if types don't match
+    ///
+    /// ```rust
+    /// # use better_any::{Tid, TidExt};
+    /// let value: Box<dyn Tid> = Box::new(5u32);
+    /// let value = value.downcast_box_or(|_| Box::new(0usize));
+    /// assert_eq!(*value, 0);
+    /// ```
+    fn downcast_box_or<T: Tid<'a>, F: FnOnce(Box<Self>) -> Box<T>>(
+        self: Box<Self>,
+        f: F,
+    ) -> Box<T> {
+        self.downcast_box().unwrap_or_else(f)
+    }
+
+    /// Attempts to downcast self to `T` behind pinned `Box` pointer
+    fn downcast_pin_box<T: Tid<'a>>(self: Pin<Box<Self>>) -> Result<Pin<Box<T>>, Pin<Box<Self>>> {
+        // SAFETY: allocation is not moved during downcast,
+        // so it is still pinned regardless of the result
+        unsafe {
+            Pin::into_inner_unchecked(self)
+                .downcast_box()
+                .map(|it| Pin::new_unchecked(it))
+                .map_err(|it| Pin::new_unchecked(it))
+        }
+    }
+
+    /// Attempts to downcast self to `T` behind `Box` pointer
+    ///
+    /// Same as `downcast_box` but error describes which type was expected and which was found.
+    ///
+    /// ```rust
+    /// # use better_any::{tid, Tid, TidExt};
+    /// struct S(usize);
+    /// tid!(S);
+    ///
+    /// let value: Box<dyn Tid> = Box::new(S(5));
+    /// let err = value.downcast::<usize>().err().unwrap();
+    /// assert!(err.to_string().starts_with("expected `usize`, found `"));
+    /// assert_eq!(err.value.downcast::<S>().unwrap().0, 5);
+    /// ```
+    ///
+    /// This is what `TryFrom<Box<dyn Tid>> for Box<T>` would be, but such implementation
+    /// is forbidden by orphan rules because `Box` is fundamental and `T` is not covered.
+    fn downcast<T: Tid<'a>>(self: Box<Self>) -> Result<Box<T>, TidDowncastError<Box<Self>>> {
+        let actual = self.self_id();
+        let actual_name = self.type_name();
+        self.downcast_box().map_err(|value| TidDowncastError {
+            value,
+            expected: T::id(),
+            actual,
+            expected_name: core::any::type_name::<T>(),
+            actual_name,
+        })
+    }
+
+    /// Same as `downcast_ref` but error describes which type was expected and which was found.
+    ///
+    /// ```rust
+    /// # use better_any::{tid, Tid, TidExt};
+    /// struct S(usize);
+    /// tid!(S);
+    ///
+    /// let value = &S(5) as &dyn Tid;
+    /// assert_eq!(value.downcast_ref_checked::<S>().unwrap().0, 5);
+    /// let err = value.downcast_ref_checked::<usize>().unwrap_err();
+    /// assert_eq!(err.expected_name, "usize");
+    /// assert!(err.to_string().starts_with("expected `usize`, found `"));
+    /// ```
+    fn downcast_ref_checked<'b, T: Tid<'a>>(&'b self) -> Result<&'b T, TypeMismatch> {
+        let actual = self.self_id();
+        let actual_name = self.type_name();
+        self.downcast_ref().ok_or(TidDowncastError {
+            value: (),
+            expected: T::id(),
+            actual,
+            expected_name: core::any::type_name::<T>(),
+            actual_name,
+        })
+    }
+
+    /// Downcasts to newtype `W` and returns reference to its inner value
+    ///
+    /// ```rust
+    /// # use better_any::{tid, Tid, TidExt, TidTransparent};
+    /// #[repr(transparent)]
+    /// struct Meters(f64);
+    /// tid!(Meters);
+    /// // or `#[derive(TidTransparent)]`
+    /// unsafe impl<'a> TidTransparent<'a> for Meters {
+    ///     type Inner = f64;
+    /// }
+    ///
+    /// let value = &Meters(5.0) as &dyn Tid;
+    /// assert_eq!(value.downcast_transparent_ref::<Meters>(), Some(&5.0));
+    /// assert!(value.downcast_ref::<f64>().is_none());
+    /// ```
+    fn downcast_transparent_ref<'b, W: TidTransparent<'a>>(&'b self) -> Option<&'b W::Inner> {
+        // SAFETY: `TidTransparent` guarantees that `W` has the same layout as `W::Inner`
+        self.downcast_ref::<W>()
+            .map(|it| unsafe { &*(it as *const W as *const W::Inner) })
+    }
+
+    /// See `downcast_transparent_ref`
+    fn downcast_transparent_mut<'b, W: TidTransparent<'a>>(
+        &'b mut self,
+    ) -> Option<&'b mut W::Inner> {
+        // SAFETY: `TidTransparent` guarantees that `W` has the same layout as `W::Inner`
+        self.downcast_mut::<W>()
+            .map(|it| unsafe { &mut *(it as *mut W as *mut W::Inner) })
+    }
+
+    /// See `downcast_transparent_ref`
+    fn downcast_transparent_box<W: TidTransparent<'a>>(
+        self: Box<Self>,
+    ) -> Result<Box<W::Inner>, Box<Self>> {
+        // SAFETY: `TidTransparent` guarantees that `W` has the same layout as `W::Inner`
+        self.downcast_box::<W>()
+            .map(|it| unsafe { Box::from_raw(Box::into_raw(it) as *mut W::Inner) })
+    }
+
+    /// Boxes `self` into `Box<dyn Tid>`
+    ///
+    /// Shorter version of `Box::new(value) as Box<dyn Tid>`.
+    /// Blanket `From<T>` implementation is impossible because it would conflict with other `From` implementations.
+    /// Note that if `self` is already `Box<dyn Tid>` it will be boxed again.
+    ///
+    /// ```rust
+    /// # use better_any::{tid, Tid, TidExt};
+    /// struct S(usize);
+    /// tid!(S);
+    /// let values = vec![S(1).into_dyn_box(), 2usize.into_dyn_box()];
+    /// assert_eq!((*values[0]).downcast_ref::<S>().unwrap().0, 1);
+    /// ```
+    fn into_dyn_box(self) -> Box<dyn Tid<'a> + 'a>
+    where
+        Self: Sized,
+    {
+        Box::new(self)
+    }
+
+    /// Same as `into_dyn_box` but for `Rc`
+    fn into_dyn_rc(self) -> Rc<dyn Tid<'a> + 'a>
+    where
+        Self: Sized,
+    {
+        Rc::new(self)
+    }
+
+    /// Same as `into_dyn_box` but for `Arc`
+    fn into_dyn_arc(self) -> Arc<dyn Tid<'a> + 'a>
+    where
+        Self: Sized,
+    {
+        Arc::new(self)
+    }
+
     /// Attempts to downcast owned `Self` to `T`,
     /// useful only in generic context as a workaround for specialization
     fn downcast_move<T: Tid<'a>>(self) -> Option<T>
+    where
+        Self: Sized,
+    {
+        self.try_downcast_move().ok()
+    }
+
+    /// Same as `downcast_move` but returns `self` back if it is not `T`
+    ///
+    /// ```rust
+    /// # use better_any::TidExt;
+    /// fn describe<'a, T: TidExt<'a>>(value: T) -> String {
+    ///     match value.try_downcast_move::<String>() {
+    ///         Ok(string) => string,
+    ///         Err(value) => match value.try_downcast_move::<usize>() {
+    ///             Ok(num) => num.to_string(),
+    ///             Err(_) => "unknown".to_string(),
+    ///         },
+    ///     }
+    /// }
+    /// assert_eq!(describe(5usize), "5");
+    /// assert_eq!(describe(5u8), "unknown");
+    /// ```
+    fn try_downcast_move<T: Tid<'a>>(self) -> Result<T, Self>
     where
         Self: Sized,
     {
         if self.is::<T>() {
             // can't use `Option` trick here like with `Any`
-            let this = core::mem::MaybeUninit::new(self);
-            return Some(unsafe { core::mem::transmute_copy(&this) });
+            let this = core::mem::ManuallyDrop::new(self);
+            return Ok(unsafe { core::mem::transmute_copy(&*this) });
         }
-        None
+        Err(self)
     }
 }
 impl<'a, X: ?Sized + Tid<'a>> TidExt<'a> for X {}
 
+/// Alias for `ToOwned` types which owned version is `TidAble`
+///
+/// Bound `T::Owned: TidAble<'a>` can't be used directly in generic code without repeating it everywhere,
+/// so use `T: TidToOwned<'a>` instead.
+///
+/// ```rust
+/// # use better_any::{Tid, TidExt, TidToOwned};
+/// fn owned<'b, 'a, T: ?Sized + TidToOwned<'a>>(value: &'b dyn Tid<'a>) -> Option<&'b T::Owned> {
+///     value.downcast_ref::<T::Owned>()
+/// }
+/// let value = String::from("owned");
+/// assert_eq!(owned::<str>(&value).unwrap(), "owned");
+/// assert!(owned::<[u8]>(&value).is_none());
+/// ```
+pub trait TidToOwned<'a>: ToOwned<Owned = <Self as TidToOwned<'a>>::TidOwned> {
+    /// Same as `ToOwned::Owned`
+    type TidOwned: TidAble<'a> + core::borrow::Borrow<Self>;
+}
+
+impl<'a, T: ?Sized + ToOwned> TidToOwned<'a> for T
+where
+    T::Owned: TidAble<'a>,
+{
+    type TidOwned = T::Owned;
+}
+
+/// Newtypes that can be downcast directly to their only field via `TidExt::downcast_transparent_*` methods
+///
+/// # Safety
+///
+/// `Self` must be a `#[repr(transparent)]` wrapper over `Inner`.
+/// Use `#[derive(TidTransparent)]` to implement it safely.
+pub unsafe trait TidTransparent<'a>: TidAble<'a> {
+    /// Type of the wrapped field
+    type Inner: 'a;
+}
+
+/// Error returned by [`TidExt::downcast`] and [`swap_erased`] if value is not of requested type
+pub struct TidDowncastError<V> {
+    /// Original value
+    pub value: V,
+    /// Type id of requested type
+    pub expected: TypeId,
+    /// Type id of the actual type of the value
+    pub actual: TypeId,
+    /// Name of requested type
+    pub expected_name: &'static str,
+    /// Name of the actual type of the value
+    pub actual_name: &'static str,
+}
+
+/// Error of downcasts that don't return original value, like [`TidExt::downcast_ref_checked`]
+pub type TypeMismatch = TidDowncastError<()>;
+
+impl<V> core::fmt::Debug for TidDowncastError<V> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TidDowncastError")
+            .field("expected", &self.expected_name)
+            .field("actual", &self.actual_name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<V> core::fmt::Display for TidDowncastError<V> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "expected `{}`, found `{}`",
+            self.expected_name, self.actual_name
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl<V> std::error::Error for TidDowncastError<V> {}
+
 /// Methods here are implemented as an associated functions because otherwise
 /// for one they will conflict with methods defined on `dyn Any` in stdlib,
 /// for two they will be available on almost every type in the program causing confusing bugs and error messages
@@ -262,6 +805,10 @@ impl<T: ?Sized + Any> AnyExt for T {}
 /// In all other places use `TidAble<'a>`.
 ///
 /// Lifetime here is necessary to make `dyn Tid<'a> + 'a` invariant over `'a`.
+///
+/// # Safety
+///
+/// It is implemented via blanket implementation over `TidAble<'a>`, implement `TidAble` instead.
 pub unsafe trait Tid<'a>: 'a {
     /// Returns type id of the type of `self`
     ///
@@ -273,6 +820,20 @@ pub unsafe trait Tid<'a>: 'a {
     fn id() -> TypeId
     where
         Self: Sized;
+
+    /// Returns name of the type of `self`
+    ///
+    /// Same as `core::any::type_name` of the concrete type behind trait object, so it has the same caveats.
+    /// Name of the type is the one that was used to create `dyn Tid`, even if it was created via `From` implementations.
+    ///
+    /// ```rust
+    /// # use better_any::{tid, Tid};
+    /// struct S;
+    /// tid!(S);
+    /// let value = &S as &dyn Tid;
+    /// assert!(value.type_name().ends_with("S"));
+    /// ```
+    fn type_name(&self) -> &'static str;
 }
 
 unsafe impl<'a, T: ?Sized + TidAble<'a>> Tid<'a> for T {
@@ -288,6 +849,11 @@ unsafe impl<'a, T: ?Sized + TidAble<'a>> Tid<'a> for T {
     {
         adjust_id::<T::Static>()
     }
+
+    #[inline]
+    fn type_name(&self) -> &'static str {
+        self.__type_name()
+    }
 }
 
 #[inline(always)]
@@ -303,6 +869,96 @@ pub fn typeid_of<'a, T: ?Sized + TidAble<'a>>() -> TypeId {
     adjust_id::<T::Static>()
 }
 
+/// Attempts to downcast `obj` to `T` where `obj` is any `Tid` type, usually trait object with `Tid` super trait
+///
+/// Same as `TidExt::downcast_ref` but can be more convenient in generic code,
+/// when trait of the trait object is only known as a type parameter.
+///
+/// ```rust
+/// # use better_any::{downcast_via, tid, Tid};
+/// trait Trait<'a>: Tid<'a> {}
+/// struct S(usize);
+/// tid!(S);
+/// impl<'a> Trait<'a> for S {}
+///
+/// fn get<'a, Tr: ?Sized + Tid<'a>>(obj: &Tr) -> usize {
+///     downcast_via::<Tr, S>(obj).map_or(0, |it| it.0)
+/// }
+/// assert_eq!(get(&S(5) as &dyn Trait), 5);
+/// ```
+#[inline]
+pub fn downcast_via<'a, Tr, T>(obj: &Tr) -> Option<&T>
+where
+    Tr: ?Sized + Tid<'a>,
+    T: Tid<'a>,
+{
+    obj.downcast_ref()
+}
+
+/// Takes box out of `opt` and downcasts it to `T` only if it contains value of type `T`
+///
+/// On type mismatch `opt` is left untouched.
+///
+/// ```rust
+/// # use better_any::{downcast_in_option, Tid};
+/// let mut opt: Option<Box<dyn Tid>> = Some(Box::new(5usize));
+/// assert!(downcast_in_option::<u8>(&mut opt).is_none());
+/// assert_eq!(downcast_in_option::<usize>(&mut opt), Some(Box::new(5)));
+/// assert!(opt.is_none());
+/// ```
+pub fn downcast_in_option<'a, T: Tid<'a>>(
+    opt: &mut Option<Box<dyn Tid<'a> + 'a>>,
+) -> Option<Box<T>> {
+    if opt.as_deref().is_some_and(|it| it.is::<T>()) {
+        opt.take().and_then(|it| it.downcast_box().ok())
+    } else {
+        None
+    }
+}
+
+/// Swaps values behind `a` and `b` if they have the same concrete type.
+///
+/// Returns error with type ids and names of both values otherwise.
+///
+/// ```rust
+/// # use better_any::{swap_erased, tid, Tid, TidExt};
+/// struct S(usize);
+/// tid!(S);
+/// let (mut a, mut b, mut c) = (S(1), S(2), 3usize);
+/// swap_erased(&mut a as &mut dyn Tid, &mut b as &mut dyn Tid).unwrap();
+/// assert_eq!((a.0, b.0), (2, 1));
+/// assert!(swap_erased(&mut a as &mut dyn Tid, &mut c as &mut dyn Tid).is_err());
+/// ```
+pub fn swap_erased<'a>(
+    a: &mut (dyn Tid<'a> + 'a),
+    b: &mut (dyn Tid<'a> + 'a),
+) -> Result<(), TypeMismatch> {
+    if a.self_id() != b.self_id() {
+        return Err(TidDowncastError {
+            value: (),
+            expected: a.self_id(),
+            actual: b.self_id(),
+            expected_name: a.type_name(),
+            actual_name: b.type_name(),
+        });
+    }
+    let size = core::mem::size_of_val(a);
+    // SAFETY: type ids are the same so both values have the same `Sized` type,
+    // and they can't overlap because both are behind mutable references
+    unsafe {
+        core::ptr::swap_nonoverlapping(a as *mut _ as *mut u8, b as *mut _ as *mut u8, size);
+    }
+    Ok(())
+}
+
+/// Returns type id that `dyn Tid` has when it was created from `T` via `From` implementations
+///
+/// It is always different from `typeid_of::<T>()`.
+#[inline]
+pub fn any_bridge_id<T: ?Sized + Any>() -> TypeId {
+    typeid_of::<TypeIdAdjuster<T>>()
+}
+
 impl<'a, T: Any> From<Box<T>> for Box<dyn Tid<'a> + 'a> {
     #[inline]
     fn from(f: Box<T>) -> Self {
@@ -311,6 +967,27 @@ impl<'a, T: Any> From<Box<T>> for Box<dyn Tid<'a> + 'a> {
     }
 }
 
+impl<'a, T: Any + Send> From<Box<T>> for Box<dyn Tid<'a> + Send + 'a> {
+    #[inline]
+    fn from(f: Box<T>) -> Self {
+        unsafe { Box::from_raw(Box::into_raw(f) as *mut TypeIdAdjuster<T>) as _ }
+    }
+}
+
+impl<'a, T: Any + Sync> From<Box<T>> for Box<dyn Tid<'a> + Sync + 'a> {
+    #[inline]
+    fn from(f: Box<T>) -> Self {
+        unsafe { Box::from_raw(Box::into_raw(f) as *mut TypeIdAdjuster<T>) as _ }
+    }
+}
+
+impl<'a, T: Any + Send + Sync> From<Box<T>> for Box<dyn Tid<'a> + Send + Sync + 'a> {
+    #[inline]
+    fn from(f: Box<T>) -> Self {
+        unsafe { Box::from_raw(Box::into_raw(f) as *mut TypeIdAdjuster<T>) as _ }
+    }
+}
+
 impl<'a: 'b, 'b, T: Any> From<&'b T> for &'b (dyn Tid<'a> + 'a) {
     #[inline]
     fn from(f: &'b T) -> Self {
@@ -325,6 +1002,48 @@ impl<'a: 'b, 'b, T: Any> From<&'b mut T> for &'b mut (dyn Tid<'a> + 'a) {
     }
 }
 
+/// Converts `Box<dyn Any>` into `Box<dyn Tid>`
+///
+/// Unlike `From` implementations concrete type is not known here,
+/// so value is boxed once more and `downcast_any_*` methods look inside of that box.
+/// Result is `dyn Tid<'static>` because `dyn Any` is always `'static`.
+///
+/// ```rust
+/// # use better_any::{into_tid_box, Tid};
+/// # use std::any::Any;
+/// let any: Box<dyn Any> = Box::new(5usize);
+/// let tid = into_tid_box(any);
+/// assert_eq!(tid.downcast_any_ref::<usize>(), Some(&5));
+/// let tid = tid.downcast_any_box::<u8>().err().unwrap();
+/// assert_eq!(*tid.downcast_any_box::<usize>().ok().unwrap(), 5);
+/// ```
+pub fn into_tid_box(value: Box<dyn Any>) -> Box<dyn Tid<'static>> {
+    Box::new(TypeIdAdjuster(value))
+}
+
+/// Converts `&dyn Any` into `Box<dyn Tid>`, use `downcast_any_ref` to get back concrete type.
+///
+/// Reinterpreting `&dyn Any` as `&dyn Tid` in place is unsound because their vtables are unrelated,
+/// so instead reference itself is boxed into a private wrapper that has its own type id.
+/// `downcast_any_ref` recognizes the wrapper and downcasts via `Any::downcast_ref`,
+/// so concrete type is always checked by `Any` itself.
+///
+/// ```rust
+/// # use better_any::Tid;
+/// # use std::any::Any;
+/// let value = 5usize;
+/// let any = &value as &dyn Any;
+/// let tid: Box<dyn Tid> = any.into();
+/// assert_eq!(tid.downcast_any_ref::<usize>(), Some(&5));
+/// assert!(tid.downcast_any_ref::<u8>().is_none());
+/// ```
+impl<'b> From<&'b dyn Any> for Box<dyn Tid<'b> + 'b> {
+    #[inline]
+    fn from(f: &'b dyn Any) -> Self {
+        Box::new(AnyRefAdjuster(f))
+    }
+}
+
 // Reverse is possible only for 'static
 // because otherwise even though user can't access type with lifetime because of different type id
 // drop still can be called after the end of lifetime.
@@ -338,12 +1057,41 @@ impl<'a: 'b, 'b, T: Any> From<&'b mut T> for &'b mut (dyn Tid<'a> + 'a) {
 #[repr(transparent)]
 struct TypeIdAdjuster<T: ?Sized>(T);
 
-tid! {impl<'a,T:'static> TidAble<'a> for TypeIdAdjuster<T> where T:?Sized}
+unsafe impl<'a, T: ?Sized + Any> TidAble<'a> for TypeIdAdjuster<T> {
+    type Static = TypeIdAdjuster<T>;
+
+    fn __type_name(&self) -> &'static str {
+        core::any::type_name::<T>()
+    }
+}
+
+// wrapper to make `&dyn Any` work with `dyn Tid`, see `From<&dyn Any>`
+struct AnyRefAdjuster<'a>(&'a dyn Any);
+
+unsafe impl<'a> TidAble<'a> for AnyRefAdjuster<'a> {
+    type Static = AnyRefAdjuster<'static>;
+
+    fn __type_name(&self) -> &'static str {
+        core::any::type_name::<dyn Any>()
+    }
+}
+
+// `Any::type_id` of the value that was erased to `dyn Any` before conversion to `dyn Tid`,
+// registries need it because wrappers have the same type id whatever is inside
+#[cfg(feature = "std")]
+pub(crate) fn erased_any_id<'a>(value: &(dyn Tid<'a> + 'a)) -> Option<TypeId> {
+    if let Some(x) = value.downcast_ref::<AnyRefAdjuster<'a>>() {
+        return Some((*x.0).type_id());
+    }
+    value
+        .downcast_ref::<TypeIdAdjuster<Box<dyn Any>>>()
+        .map(|x| (*x.0).type_id())
+}
 
 impl<'a> dyn Tid<'a> + 'a {
     /// Tries to downcast `dyn Tid` to `T`
     ///
-    /// Use it only if `dyn Tid` was created from concrete `T:Any` via `From` implementations.
+    /// Use it only if `dyn Tid` was created from concrete `T:Any` or `&dyn Any` via `From` implementations or `into_tid_box`.
     /// See examples how it does relate to other downcast methods
     ///
     /// ```rust
@@ -364,36 +1112,232 @@ impl<'a> dyn Tid<'a> + 'a {
     #[inline]
     pub fn downcast_any_ref<T: Any>(&self) -> Option<&T> {
         // SAFETY: just a transparent reference cast
-        self.downcast_ref::<TypeIdAdjuster<T>>()
-            .map(|x| unsafe { &*(x as *const _ as *const T) })
+        if let Some(x) = self.downcast_ref::<TypeIdAdjuster<T>>() {
+            return Some(unsafe { &*(x as *const _ as *const T) });
+        }
+        if let Some(x) = self.downcast_ref::<AnyRefAdjuster<'a>>() {
+            return x.0.downcast_ref();
+        }
+        self.downcast_ref::<TypeIdAdjuster<Box<dyn Any>>>()
+            .and_then(|x| x.0.downcast_ref())
+    }
+
+    /// Returns true if `dyn Tid` was created from `T: Any` via `From` implementations or `into_tid_box`
+    ///
+    /// Same as `downcast_any_ref::<T>().is_some()`, `is` can't be used for that
+    /// because such values have type id of the private wrapper instead of `T`.
+    ///
+    /// ```rust
+    /// # use better_any::{Tid, TidAble, TidExt, tid};
+    /// struct S;
+    /// tid!(S);
+    ///
+    /// let from_any: &dyn Tid = (&S).into();
+    /// assert!(from_any.is_any::<S>());
+    /// assert!(!from_any.is::<S>());
+    /// assert!(!(&S as &dyn Tid).is_any::<S>());
+    /// ```
+    #[inline]
+    pub fn is_any<T: Any>(&self) -> bool {
+        if self.is::<TypeIdAdjuster<T>>() {
+            return true;
+        }
+        if let Some(x) = self.downcast_ref::<AnyRefAdjuster<'a>>() {
+            return x.0.is::<T>();
+        }
+        self.downcast_ref::<TypeIdAdjuster<Box<dyn Any>>>()
+            .is_some_and(|x| (*x.0).is::<T>())
     }
 
     /// See `downcast_any_ref`
     #[inline]
     pub fn downcast_any_mut<T: Any>(&mut self) -> Option<&mut T> {
-        // SAFETY: just a transparent reference cast
-        self.downcast_mut::<TypeIdAdjuster<T>>()
-            .map(|x| unsafe { &mut *(x as *mut _ as *mut T) })
+        if self.is::<TypeIdAdjuster<T>>() {
+            // SAFETY: just a transparent reference cast
+            return self
+                .downcast_mut::<TypeIdAdjuster<T>>()
+                .map(|x| unsafe { &mut *(x as *mut _ as *mut T) });
+        }
+        self.downcast_mut::<TypeIdAdjuster<Box<dyn Any>>>()
+            .and_then(|x| x.0.downcast_mut())
     }
 
     /// See `downcast_any_ref`
     #[inline]
     pub fn downcast_any_box<T: Any>(self: Box<Self>) -> Result<Box<T>, Box<Self>> {
         // SAFETY: just a transparent reference cast
-        self.downcast_box::<TypeIdAdjuster<T>>()
-            .map(|x| unsafe { Box::from_raw(Box::into_raw(x) as *mut T) as _ })
+        let this = match self.downcast_box::<TypeIdAdjuster<T>>() {
+            Ok(x) => return Ok(unsafe { Box::from_raw(Box::into_raw(x) as *mut T) as _ }),
+            Err(this) => this,
+        };
+        match this.downcast_box::<TypeIdAdjuster<Box<dyn Any>>>() {
+            Ok(x) => x.0.downcast().map_err(|x| Box::new(TypeIdAdjuster(x)) as _),
+            Err(this) => Err(this),
+        }
+    }
+
+    /// Creates `Rc<dyn Tid>` from `Rc<T>` where `T: Any`
+    ///
+    /// Same as `From` implementations for `Box` and references,
+    /// which are impossible for `Rc` because of the orphan rules.
+    /// Use `downcast_any_*` methods to get back concrete type.
+    ///
+    /// ```rust
+    /// # use better_any::Tid;
+    /// # use std::rc::Rc;
+    /// let rc = <dyn Tid>::from_any_rc(Rc::new(5usize));
+    /// assert_eq!(*rc.downcast_any_rc::<usize>().ok().unwrap(), 5);
+    /// ```
+    #[inline]
+    pub fn from_any_rc<T: Any>(value: Rc<T>) -> Rc<Self> {
+        // TypeIdAdjuster is a transparent wrapper so it is sound
+        unsafe { Rc::from_raw(Rc::into_raw(value) as *const TypeIdAdjuster<T>) as _ }
+    }
+
+    /// Creates `Arc<dyn Tid>` from `Arc<T>` where `T: Any`
+    ///
+    /// See `from_any_rc`
+    #[inline]
+    pub fn from_any_arc<T: Any>(value: Arc<T>) -> Arc<Self> {
+        unsafe { Arc::from_raw(Arc::into_raw(value) as *const TypeIdAdjuster<T>) as _ }
+    }
+
+    /// See `downcast_any_ref`
+    #[inline]
+    pub fn downcast_any_rc<T: Any>(self: Rc<Self>) -> Result<Rc<T>, Rc<Self>> {
+        // SAFETY: just a transparent reference cast
+        self.downcast_rc::<TypeIdAdjuster<T>>()
+            .map(|x| unsafe { Rc::from_raw(Rc::into_raw(x) as *const T) })
+    }
+
+    /// See `downcast_any_ref`
+    #[inline]
+    pub fn downcast_any_arc<T: Any>(self: Arc<Self>) -> Result<Arc<T>, Arc<Self>> {
+        // SAFETY: just a transparent reference cast
+        self.downcast_arc::<TypeIdAdjuster<T>>()
+            .map(|x| unsafe { Arc::from_raw(Arc::into_raw(x) as *const T) })
+    }
+
+    /// Returns layout of the concrete type behind `dyn Tid`
+    ///
+    /// ```rust
+    /// # use better_any::Tid;
+    /// # use core::alloc::Layout;
+    /// let value: Box<dyn Tid> = Box::new(5u16);
+    /// assert_eq!(value.erased_layout(), Layout::new::<u16>());
+    /// ```
+    #[inline]
+    pub fn erased_layout(&self) -> core::alloc::Layout {
+        core::alloc::Layout::for_value(self)
+    }
+
+    /// Downcasts `dyn Tid` to `HashSet<T>` and checks whether it contains `value`.
+    ///
+    /// Returns `None` if type behind `dyn Tid` is not a `HashSet<T>` with default hasher.
+    ///
+    /// ```rust
+    /// # use better_any::{Tid, TidExt, tid};
+    /// # use std::collections::HashSet;
+    /// #[derive(Hash, Eq, PartialEq)]
+    /// struct S(usize);
+    /// tid!(S);
+    ///
+    /// let set = vec![S(1)].into_iter().collect::<HashSet<_>>();
+    /// let set = &set as &dyn Tid;
+    /// assert_eq!(set.downcast_hashset_contains(&S(1)), Some(true));
+    /// assert_eq!(set.downcast_hashset_contains(&S(2)), Some(false));
+    /// ```
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn downcast_hashset_contains<T: TidAble<'a> + Eq + Hash>(&self, value: &T) -> Option<bool> {
+        self.downcast_ref::<HashSet<T>>()
+            .map(|set| set.contains(value))
+    }
+
+    /// Downcasts `dyn Tid` to fixed size array `[T; N]`
+    ///
+    /// Returns `None` if type behind `dyn Tid` is not an array of exactly `N` elements of `T`.
+    ///
+    /// ```rust
+    /// # use better_any::Tid;
+    /// let array: Box<dyn Tid> = Box::new([1u8, 2, 3]);
+    /// assert_eq!(array.downcast_array_slice::<u8, 3>(), Some(&[1, 2, 3]));
+    /// assert!(array.downcast_array_slice::<u8, 2>().is_none());
+    /// ```
+    #[inline]
+    pub fn downcast_array_slice<T: TidAble<'a>, const N: usize>(&self) -> Option<&[T; N]> {
+        self.downcast_ref::<[T; N]>()
+    }
+
+    /// Downcasts `dyn Tid` to `Cell<T>` and returns copy of its value
+    ///
+    /// ```rust
+    /// # use better_any::Tid;
+    /// # use std::cell::Cell;
+    /// let cell = Cell::new(5i32);
+    /// let value = &cell as &dyn Tid;
+    /// cell.set(6);
+    /// assert_eq!(value.downcast_cell_get::<i32>(), Some(6));
+    /// assert_eq!(value.downcast_cell_get::<u32>(), None);
+    /// ```
+    #[inline]
+    pub fn downcast_cell_get<T: TidAble<'a> + Copy>(&self) -> Option<T> {
+        self.downcast_ref::<Cell<T>>().map(Cell::get)
     }
 }
 
-use std::cell::*;
-use std::rc::*;
-use std::sync::*;
+// same as above but for `Send`/`Sync` trait objects
+macro_rules! impl_downcast_any {
+    ($($bound: ident)+) => {
+        impl<'a> dyn Tid<'a> $(+ $bound)+ + 'a {
+            /// See `<dyn Tid>::downcast_any_ref`
+            #[inline]
+            pub fn downcast_any_ref<T: Any>(&self) -> Option<&T> {
+                self.downcast_ref::<TypeIdAdjuster<T>>()
+                    .map(|x| unsafe { &*(x as *const _ as *const T) })
+            }
+
+            /// See `<dyn Tid>::is_any`
+            #[inline]
+            pub fn is_any<T: Any>(&self) -> bool {
+                self.is::<TypeIdAdjuster<T>>()
+            }
+
+            /// See `<dyn Tid>::downcast_any_ref`
+            #[inline]
+            pub fn downcast_any_mut<T: Any>(&mut self) -> Option<&mut T> {
+                self.downcast_mut::<TypeIdAdjuster<T>>()
+                    .map(|x| unsafe { &mut *(x as *mut _ as *mut T) })
+            }
+
+            /// See `<dyn Tid>::downcast_any_ref`
+            #[inline]
+            pub fn downcast_any_box<T: Any>(self: Box<Self>) -> Result<Box<T>, Box<Self>> {
+                self.downcast_box::<TypeIdAdjuster<T>>()
+                    .map(|x| unsafe { Box::from_raw(Box::into_raw(x) as *mut T) as _ })
+            }
+        }
+    };
+}
+
+impl_downcast_any!(Send);
+impl_downcast_any!(Sync);
+impl_downcast_any!(Send Sync);
+
+use core::cell::{Cell, RefCell};
+#[cfg(feature = "std")]
+use std::sync::{Mutex, RwLock};
 tid!(impl<'a, T> TidAble<'a> for Box<T> where T:?Sized);
-tid!(impl<'a, T> TidAble<'a> for Rc<T>);
+// `Pin<&'a mut T>` is covered by this impl together with `&'a mut T` for `T: 'static`,
+// separate impl for non-static `T` would overlap with it
+tid!(impl<'a, P> TidAble<'a> for Pin<P>);
+tid!(impl<'a, T> TidAble<'a> for Rc<T> where T:?Sized);
 tid!(impl<'a, T> TidAble<'a> for RefCell<T>);
 tid!(impl<'a, T> TidAble<'a> for Cell<T>);
-tid!(impl<'a, T> TidAble<'a> for Arc<T>);
+tid!(impl<'a, T> TidAble<'a> for Arc<T> where T:?Sized);
+#[cfg(feature = "std")]
 tid!(impl<'a, T> TidAble<'a> for Mutex<T>);
+#[cfg(feature = "std")]
 tid!(impl<'a, T> TidAble<'a> for RwLock<T>);
 
 // tid! {impl<'a, T> TidAble<'a> for Option<T>}
@@ -406,11 +1350,237 @@ const _: () = {
     }
 };
 
+// tid! doesn't support const parameters
+const _: () = {
+    use core::marker::PhantomData;
+    pub struct __TypeIdGenerator<T: ?Sized, const N: usize>(PhantomData<T>);
+    unsafe impl<'a, T: TidAble<'a>, const N: usize> TidAble<'a> for [T; N] {
+        type Static = __TypeIdGenerator<T::Static, N>;
+    }
+};
+
+const _: () = {
+    use core::array::IntoIter;
+    use core::marker::PhantomData;
+    pub struct __TypeIdGenerator<T: ?Sized, const N: usize>(PhantomData<T>);
+    unsafe impl<'a, T: TidAble<'a>, const N: usize> TidAble<'a> for IntoIter<T, N> {
+        type Static = __TypeIdGenerator<T::Static, N>;
+    }
+};
+
+// also covers tuples of references like `(&'a A, &'a B)`,
+// since `&'a A` is `TidAble<'a>` for `A: 'static`
+macro_rules! tid_tuple {
+    ($($param: ident),+) => {
+        tid! { impl<'a, $($param),+> TidAble<'a> for ($($param,)+) }
+    };
+}
+
+unsafe impl<'a> TidAble<'a> for () {
+    type Static = ();
+}
+tid_tuple!(A);
+tid_tuple!(A, B);
+tid_tuple!(A, B, C);
+tid_tuple!(A, B, C, D);
+tid_tuple!(A, B, C, D, E);
+tid_tuple!(A, B, C, D, E, F);
+tid_tuple!(A, B, C, D, E, F, G);
+tid_tuple!(A, B, C, D, E, F, G, H);
+tid_tuple!(A, B, C, D, E, F, G, H, I);
+tid_tuple!(A, B, C, D, E, F, G, H, I, J);
+tid_tuple!(A, B, C, D, E, F, G, H, I, J, K);
+tid_tuple!(A, B, C, D, E, F, G, H, I, J, K, L);
+
+// only non higher-ranked function pointers are covered,
+// i.e. `fn(&'a str)` is `TidAble<'a>` but `for<'x> fn(&'x str)` is not
+macro_rules! tid_fn {
+    ($($param: ident),*) => {
+        tid! { impl<'a, $($param,)* R> TidAble<'a> for fn($($param),*) -> R }
+    };
+}
+
+tid_fn!();
+tid_fn!(A);
+tid_fn!(A, B);
+tid_fn!(A, B, C);
+tid_fn!(A, B, C, D);
+tid_fn!(A, B, C, D, E);
+tid_fn!(A, B, C, D, E, F);
+
+tid! { impl<'a, T> TidAble<'a> for core::marker::PhantomData<T> where T: ?Sized }
+
+use alloc::vec::Vec;
 tid! {impl<'a, T> TidAble<'a> for Vec<T>}
 
+use alloc::collections::{BinaryHeap, LinkedList, VecDeque};
+tid! { impl<'a, T> TidAble<'a> for VecDeque<T> }
+tid! { impl<'a, T> TidAble<'a> for LinkedList<T> }
+tid! { impl<'a, T> TidAble<'a> for BinaryHeap<T> }
+
+#[cfg(feature = "std")]
+use core::hash::Hash;
+#[cfg(feature = "std")]
+use std::collections::HashSet;
+#[cfg(feature = "std")]
+tid! { impl<'a, T, S: 'static> TidAble<'a> for HashSet<T, S> }
+
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+tid! { impl<'a, K, V, S: 'static> TidAble<'a> for HashMap<K, V, S> }
+#[cfg(feature = "std")]
+tid! { impl<'a, K, V> TidAble<'a> for std::collections::hash_map::IntoIter<K, V> }
+// borrowed iterator is only `Tid` for its own lifetime, like `Cow<'a, T>`
+#[cfg(feature = "std")]
+tid! { impl<'a, K, V> TidAble<'a> for std::collections::hash_map::Iter<'a, K, V> }
+
+use alloc::collections::{BTreeMap, BTreeSet};
+tid! { impl<'a, T> TidAble<'a> for BTreeSet<T> }
+tid! { impl<'a, K, V> TidAble<'a> for BTreeMap<K, V> }
+
 tid! { impl<'a,T,E> TidAble<'a> for Result<T,E> }
 
+tid! { impl<'a, T> TidAble<'a> for [T] }
+
+use alloc::borrow::{Cow, ToOwned};
+tid! { impl<'a, T> TidAble<'a> for Cow<'a, T> where T: ?Sized + ToOwned }
+
+tid!(str);
+
+// primitives are `Any` but it is still useful for them to be `TidAble`
+// so they can be used as type parameters of other `Tid` types
+tid!(bool);
+tid!(char);
+tid!(u8);
+tid!(u16);
+tid!(u32);
+tid!(u64);
+tid!(u128);
+tid!(usize);
+tid!(i8);
+tid!(i16);
+tid!(i32);
+tid!(i64);
+tid!(i128);
+tid!(isize);
+tid!(f32);
+tid!(f64);
+use alloc::string::String;
+tid!(String);
+
+use core::time::Duration;
+tid!(Duration);
+
+#[cfg(target_has_atomic = "8")]
+use core::sync::atomic::{AtomicBool, AtomicI8, AtomicU8};
+#[cfg(target_has_atomic = "8")]
+tid!(AtomicBool, AtomicI8, AtomicU8);
+#[cfg(target_has_atomic = "16")]
+use core::sync::atomic::{AtomicI16, AtomicU16};
+#[cfg(target_has_atomic = "16")]
+tid!(AtomicI16, AtomicU16);
+#[cfg(target_has_atomic = "32")]
+use core::sync::atomic::{AtomicI32, AtomicU32};
+#[cfg(target_has_atomic = "32")]
+tid!(AtomicI32, AtomicU32);
+#[cfg(target_has_atomic = "64")]
+use core::sync::atomic::{AtomicI64, AtomicU64};
+#[cfg(target_has_atomic = "64")]
+tid!(AtomicI64, AtomicU64);
+#[cfg(target_has_atomic = "ptr")]
+use core::sync::atomic::{AtomicIsize, AtomicPtr, AtomicUsize};
+#[cfg(target_has_atomic = "ptr")]
+tid!(AtomicIsize, AtomicUsize);
+#[cfg(target_has_atomic = "ptr")]
+tid! { impl<'a, T> TidAble<'a> for AtomicPtr<T> }
+
+#[cfg(feature = "saturating")]
+use core::num::Saturating;
+use core::num::Wrapping;
+#[cfg(feature = "saturating")]
+tid! { impl<'a, T> TidAble<'a> for Saturating<T> }
+tid! { impl<'a, T> TidAble<'a> for Wrapping<T> }
+
+use core::cmp::Reverse;
+tid! { impl<'a, T> TidAble<'a> for Reverse<T> }
+
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::{BufReader, BufWriter, Chain, Cursor, Take};
+#[cfg(feature = "std")]
+tid!(File);
+#[cfg(feature = "std")]
+tid! { impl<'a, T> TidAble<'a> for Cursor<T> }
+#[cfg(feature = "std")]
+tid! { impl<'a, R> TidAble<'a> for BufReader<R> }
+#[cfg(feature = "std")]
+tid! { impl<'a, W> TidAble<'a> for BufWriter<W> where W: std::io::Write }
+#[cfg(feature = "std")]
+tid! { impl<'a, R> TidAble<'a> for Take<R> }
+#[cfg(feature = "std")]
+tid! { impl<'a, T, U> TidAble<'a> for Chain<T, U> }
+
+#[cfg(feature = "std")]
+use std::thread::JoinHandle;
+#[cfg(feature = "std")]
+tid! { impl<'a, T> TidAble<'a> for JoinHandle<T> }
+
 tid! { impl<'a> TidAble<'a> for dyn Tid<'a> + 'a }
+#[cfg(feature = "std")]
+use std::error::Error;
+#[cfg(feature = "std")]
+tid! { impl<'a> TidAble<'a> for dyn Error + 'a }
+#[cfg(feature = "std")]
+tid! { impl<'a> TidAble<'a> for dyn Error + Send + Sync + 'a }
+
+/// Walks `source` chain of `err` and returns the first source of type `T`
+///
+/// `err` itself is not checked. `Error::source` returns `dyn Error + 'static` which has no `Tid` vtable,
+/// so sources are checked via `Error`'s own `Any`-based downcasting and `T` has to be `'static`.
+///
+/// ```rust
+/// # use better_any::find_source_downcast;
+/// # use std::{error::Error, fmt};
+/// #[derive(Debug)]
+/// struct Leaf;
+/// impl fmt::Display for Leaf {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         f.write_str("leaf")
+///     }
+/// }
+/// impl Error for Leaf {}
+///
+///
+/// #[derive(Debug)]
+/// struct Wrapper(Leaf);
+/// impl fmt::Display for Wrapper {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         f.write_str("wrapper")
+///     }
+/// }
+/// impl Error for Wrapper {
+///     fn source(&self) -> Option<&(dyn Error + 'static)> {
+///         Some(&self.0)
+///     }
+/// }
+///
+/// let err = Wrapper(Leaf);
+/// assert!(find_source_downcast::<Leaf>(&err).is_some());
+/// assert!(find_source_downcast::<Wrapper>(&err).is_none());
+/// ```
+#[cfg(feature = "std")]
+pub fn find_source_downcast<'e, T: Error + 'static>(err: &'e (dyn Error + 'e)) -> Option<&'e T> {
+    let mut source = err.source();
+    while let Some(err) = source {
+        if let Some(found) = err.downcast_ref() {
+            return Some(found);
+        }
+        source = err.source();
+    }
+    None
+}
 
 /// Main safe implementation interface of related unsafe traits
 ///
@@ -434,6 +1604,28 @@ tid! { impl<'a> TidAble<'a> for dyn Tid<'a> + 'a }
 /// tid!{ impl<'b> TidAble<'b> for dyn Test<'b> + 'b }
 /// ```
 ///
+/// It doesn't matter whether the type is a struct, an enum or a union.
+/// Generic types with a lifetime as their first parameter also have short form:
+/// ```rust
+/// # use better_any::tid;
+/// enum Either<'a, L, R> {
+///     Left(&'a L),
+///     Right(R),
+/// }
+/// tid!(Either<'a, L, R>);
+/// // same as
+/// // tid!{ impl<'a, L, R> TidAble<'a> for Either<'a, L, R> }
+/// ```
+///
+/// Several non-generic types or types with single lifetime can be listed at once:
+/// ```rust
+/// # use better_any::tid;
+/// struct Foo;
+/// struct Bar<'a>(&'a str);
+/// struct Baz;
+/// tid!(Foo, Bar<'a>, Baz);
+/// ```
+///
 /// Implementation by default adds `TidAble<'a>` bound on all generic parameters.
 /// This behavior can be opted out by specifying `'static` bound on corresponding type parameter.
 /// Note that due to decl macro limitations it must be specified directly on type parameter
@@ -444,6 +1636,17 @@ tid! { impl<'a> TidAble<'a> for dyn Tid<'a> + 'a }
 /// tid! { impl<'a,X:'static> Tid<'a> for Test<'a,X> where X:?Sized }
 /// ```
 ///
+/// Const parameters are supported as long as their type is a single identifier:
+/// ```rust
+/// # use better_any::{tid, Tid, TidExt};
+/// struct Window<'a, T, const N: usize>(&'a [T; N]);
+/// tid! { impl<'a, T, const N: usize> TidAble<'a> for Window<'a, T, N> }
+///
+/// let value = &Window(&[1u8, 2]) as &dyn Tid;
+/// assert!(value.is::<Window<u8, 2>>());
+/// assert!(!value.is::<Window<u8, 3>>());
+/// ```
+///
 #[macro_export]
 macro_rules! tid {
 
@@ -457,54 +1660,64 @@ macro_rules! tid {
             type Static = $struct<'static>;
         }
     };
+    ($struct: ident < $lt: lifetime $(, $param: ident)+ >) => {
+        $crate::tid!{ impl<$lt $(,$param)+> TidAble<$lt> for $struct<$lt $(,$param)+> }
+    };
+    ($($struct: ident $(< $lt: lifetime >)?),+ $(,)?) => {
+        $( $crate::tid!{ $struct $(< $lt >)? } )+
+    };
     // no static parameters case
     (impl <$lt:lifetime $(,$param:ident)*> $tr:ident<$lt2:lifetime> for $($struct: tt)+ ) => {
-        $crate::tid!{ inner impl <$lt $(,$param)* static> $tr<$lt2> for $($struct)+  }
+        $crate::tid!{ inner impl <$lt $(,$param)* static const> $tr<$lt2> for $($struct)+  }
     };
 
     //todo change macro to use attributes instead of 'static
     // inner submacro is used to check/fix/error on whether correct trait is being implemented
-    (inner impl <$lt:lifetime $(,$param:ident)* static $( $static_param:ident)* > Tid<$lt2:lifetime> for $($struct: tt)+ ) => {
-        $crate::tid!{ inner impl <$lt $(,$param)* static $( $static_param)*> TidAble<$lt2> for $($struct)+  }
+    (inner impl <$lt:lifetime $(,$param:ident)* static $(,$static_param:ident)* const $(,$const_param:ident : $const_ty:ident)* > Tid<$lt2:lifetime> for $($struct: tt)+ ) => {
+        $crate::tid!{ inner impl <$lt $(,$param)* static $(,$static_param)* const $(,$const_param : $const_ty)*> TidAble<$lt2> for $($struct)+  }
     };
-    (inner impl <$lt:lifetime $(,$param:ident)* static $( $static_param:ident)* > TidAble<$lt2:lifetime> for $($struct: tt)+ ) => {
+    (inner impl <$lt:lifetime $(,$param:ident)* static $(,$static_param:ident)* const $(,$const_param:ident : $const_ty:ident)* > TidAble<$lt2:lifetime> for $($struct: tt)+ ) => {
         const _:() = {
             use core::marker::PhantomData;
-            type __Alias<$lt $(,$param)* $(,$static_param)*>  = $crate::before_where!{ $($struct)+ };
-            pub struct __TypeIdGenerator<$lt $(,$param:?Sized)* $(,$static_param:?Sized)*>
+            type __Alias<$lt $(,$param)* $(,$static_param)* $(,const $const_param: $const_ty)*>  = $crate::before_where!{ $($struct)+ };
+            pub struct __TypeIdGenerator<$lt $(,$param:?Sized)* $(,$static_param:?Sized)* $(,const $const_param: $const_ty)*>
                 (PhantomData<& $lt ()> $(,PhantomData<$param>)* $(,PhantomData<$static_param>)*);
             $crate::impl_block!{
                 after where {  $($struct)+ }
-                {unsafe impl<$lt $(,$param:$crate::TidAble<$lt>)* $(,$static_param: 'static)* > $crate::TidAble<$lt2> for __Alias<$lt $(,$param)* $(,$static_param)*>}
+                {unsafe impl<$lt $(,$param:$crate::TidAble<$lt>)* $(,$static_param: 'static)* $(,const $const_param: $const_ty)* > $crate::TidAble<$lt2> for __Alias<$lt $(,$param)* $(,$static_param)* $(,$const_param)*>}
 
                 {
-                    type Static = __TypeIdGenerator<'static $(,$param::Static)* $(,$static_param)*>;
+                    type Static = __TypeIdGenerator<'static $(,$param::Static)* $(,$static_param)* $(,$const_param)*>;
                 }
             }
         };
     };
-    (inner impl <$lt:lifetime $(,$param:ident)* static $( $static_param:ident)* > $tr:ident<$lt2:lifetime> for $($struct: tt)+ ) => {
+    (inner impl <$lt:lifetime $(,$param:ident)* static $(,$static_param:ident)* const $(,$const_param:ident : $const_ty:ident)* > $tr:ident<$lt2:lifetime> for $($struct: tt)+ ) => {
         compile_error!{" wrong trait, should be TidAble or Tid "}
     };
 
-    // temp submacro is used to separate 'static type parameters from other ones
-    (temp $(,$param:ident)* static $(,$static_param:ident)* impl <$lt:lifetime , $token:ident : 'static $($tail: tt)+ ) => {
-        $crate::tid!{ temp $(,$param)* static  $(,$static_param)* , $token  impl <$lt $($tail)+}
+    // temp submacro is used to separate 'static and const parameters from other ones
+    (temp $(,$param:ident)* static $(,$static_param:ident)* const $(,$const_param:ident : $const_ty:ident)* impl <$lt:lifetime , const $name:ident : $ty:ident $($tail: tt)+ ) => {
+        $crate::tid!{ temp $(,$param)* static $(,$static_param)* const $(,$const_param : $const_ty)* , $name : $ty impl <$lt $($tail)+}
+    };
+    (temp $(,$param:ident)* static $(,$static_param:ident)* const $(,$const_param:ident : $const_ty:ident)* impl <$lt:lifetime , $token:ident : 'static $($tail: tt)+ ) => {
+        $crate::tid!{ temp $(,$param)* static $(,$static_param)* , $token const $(,$const_param : $const_ty)* impl <$lt $($tail)+}
     };
-    (temp $(,$param:ident)* static $(,$static_param:ident)* impl <$lt:lifetime , $token:ident $($tail: tt)+ ) => {
-        $crate::tid!{ temp $(,$param)* ,$token static $(,$static_param)* impl <$lt $($tail)+ }
+    (temp $(,$param:ident)* static $(,$static_param:ident)* const $(,$const_param:ident : $const_ty:ident)* impl <$lt:lifetime , $token:ident $($tail: tt)+ ) => {
+        $crate::tid!{ temp $(,$param)* ,$token static $(,$static_param)* const $(,$const_param : $const_ty)* impl <$lt $($tail)+ }
     };
-    (temp $(,$param:ident)* static $(,$static_param:ident)* impl <$lt:lifetime> $($tail: tt)+ ) => {
-        $crate::tid!{ inner impl <$lt $(,$param)* static $( $static_param)* > $($tail)+ }
+    (temp $(,$param:ident)* static $(,$static_param:ident)* const $(,$const_param:ident : $const_ty:ident)* impl <$lt:lifetime> $($tail: tt)+ ) => {
+        $crate::tid!{ inner impl <$lt $(,$param)* static $(,$static_param)* const $(,$const_param : $const_ty)* > $($tail)+ }
     };
     // ( temp static  $($tail:tt)+ ) => {
     //     compile_error!{"invalid syntax"}
     // };
     ( impl $($tail: tt)+) => {
-        $crate::tid!{ temp static impl $($tail)+ }
+        $crate::tid!{ temp static const impl $($tail)+ }
     };
 }
 
+#[allow(dead_code)]
 struct Test<'a, X: ?Sized>(&'a str, Box<X>);
 // tid! { impl < 'a    static X    > TidAble < 'a > for Test < 'a , X > where X : ? Sized  }
 tid! { impl<'a,X:'static> TidAble<'a> for Test<'a,X> where X:?Sized }
@@ -570,6 +1783,8 @@ macro_rules! impl_block {
 
 tid! { impl<'a,T:'static> TidAble<'a> for &'a T }
 tid! { impl<'a,T:'static> TidAble<'a> for &'a mut T }
+tid! { impl<'a, T> TidAble<'a> for &'a [T] }
+tid! { impl<'a> TidAble<'a> for &'a str }
 
 /// Just an alias of `tid!` macro if someone considers that name to be more clear and for compatibility with previous versions.
 ///
@@ -587,6 +1802,142 @@ pub use tid as type_id;
 //     ($($tokens:tt)+) => { $crate::tid!{ $($tokens)+ } };
 // }
 
+/// Generates `from_tid_box` constructor for enum which variants wrap distinct `Tid` types.
+///
+/// Generated function downcasts `Box<dyn Tid>` to each listed type in order
+/// and wraps the first match into corresponding variant.
+/// If nothing matches, original box is returned back.
+///
+/// ```rust
+/// # use better_any::{tid, tid_enum_dispatch, Tid};
+/// struct Num(usize);
+/// tid!(Num);
+/// struct Name<'a>(&'a str);
+/// tid!(Name<'a>);
+///
+/// enum Value<'a> {
+///     Num(Num),
+///     Name(Name<'a>),
+/// }
+/// tid_enum_dispatch!(Value<'a> { Num => Num, Name<'a> => Name });
+///
+/// let value = Value::from_tid_box(Box::new(Name("x")));
+/// assert!(matches!(value, Ok(Value::Name(Name("x")))));
+/// assert!(Value::from_tid_box(Box::new(5usize)).is_err());
+/// ```
+#[macro_export]
+macro_rules! tid_enum_dispatch {
+    ($enum: ident < $lt: lifetime > { $($ty: ty => $variant: ident),* $(,)? }) => {
+        impl<$lt> $enum<$lt> {
+            /// Converts `Box<dyn Tid>` into the variant wrapping its concrete type
+            #[allow(dead_code)]
+            pub fn from_tid_box(
+                value: $crate::__Box<dyn $crate::Tid<$lt> + $lt>,
+            ) -> ::core::result::Result<Self, $crate::__Box<dyn $crate::Tid<$lt> + $lt>> {
+                $(
+                    let value = match $crate::TidExt::downcast_box::<$ty>(value) {
+                        ::core::result::Result::Ok(it) => return ::core::result::Result::Ok($enum::$variant(*it)),
+                        ::core::result::Result::Err(it) => it,
+                    };
+                )*
+                ::core::result::Result::Err(value)
+            }
+        }
+    };
+    ($enum: ident { $($ty: ty => $variant: ident),* $(,)? }) => {
+        impl $enum {
+            /// Converts `Box<dyn Tid>` into the variant wrapping its concrete type
+            #[allow(dead_code)]
+            pub fn from_tid_box<'a>(
+                value: $crate::__Box<dyn $crate::Tid<'a> + 'a>,
+            ) -> ::core::result::Result<Self, $crate::__Box<dyn $crate::Tid<'a> + 'a>> {
+                $(
+                    let value = match $crate::TidExt::downcast_box::<$ty>(value) {
+                        ::core::result::Result::Ok(it) => return ::core::result::Result::Ok($enum::$variant(*it)),
+                        ::core::result::Result::Err(it) => it,
+                    };
+                )*
+                ::core::result::Result::Err(value)
+            }
+        }
+    };
+}
+
+/// Downcasts `Box<dyn Tid>` to a struct type and moves out one of its fields
+///
+/// Evaluates to `Ok(field)` if value is of the given type, other fields are dropped as usual.
+/// Otherwise original box is returned back in `Err`.
+/// Like any move out of a field it doesn't work for types that implement `Drop`.
+///
+/// ```rust
+/// # use better_any::{project_field, tid, Tid};
+/// struct Request<'a> {
+///     path: &'a str,
+///     body: Vec<u8>,
+/// }
+/// tid!(Request<'a>);
+///
+/// let value: Box<dyn Tid> = Box::new(Request { path: "/", body: vec![1] });
+/// let body = project_field!(value, Request => body);
+/// assert_eq!(body.ok(), Some(vec![1]));
+///
+/// let value: Box<dyn Tid> = Box::new((1, 2));
+/// assert!(project_field!(value, Request => path).is_err());
+/// ```
+#[macro_export]
+macro_rules! project_field {
+    ($value: expr, $ty: ty => $field: tt) => {
+        match $crate::TidExt::downcast_box::<$ty>($value) {
+            ::core::result::Result::Ok(it) => {
+                let it = *it;
+                ::core::result::Result::Ok(it.$field)
+            }
+            ::core::result::Result::Err(it) => ::core::result::Result::Err(it),
+        }
+    };
+}
+
 /// unstable features that require nightly, use on your own risk
 #[cfg(feature = "nightly")]
 pub mod nightly;
+
+/// Registries that map type ids of `dyn Tid` to registered types
+#[cfg(feature = "std")]
+pub mod registry;
+
+/// Type-keyed map of `Tid` values
+#[cfg(feature = "std")]
+pub mod map;
+
+/// Lifetime-aware analog of `core::any::Provider`
+pub mod provider;
+
+/// Downcasting of values inside of `RefCell`
+pub mod cell;
+
+/// Markers to be generic over mutability of references
+pub mod borrow;
+
+/// Logging of type erased values
+pub mod field;
+
+/// Downcasting of smart pointers on stable
+pub mod cast;
+
+/// Cloning of type erased values
+pub mod clone;
+
+/// Compatibility layer for code migrating from `downcast-rs`
+pub mod downcast;
+
+/// Serialization of type erased values of registered types
+#[cfg(feature = "serde")]
+pub mod serialize;
+
+/// Dispatch on `dyn Tid` objects and its memoization
+#[cfg(feature = "std")]
+pub mod dispatch;
+
+/// Implementations for `parking_lot` locks and downcasting of their guards
+#[cfg(feature = "parking_lot")]
+pub mod parking_lot;
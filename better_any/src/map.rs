@@ -0,0 +1,303 @@
+use crate::{Tid, TidExt};
+use std::any::TypeId;
+use std::collections::hash_map::{self, HashMap};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// Map that stores at most one value of each type, keyed by its type id
+///
+/// It is invariant over `'a`, same as `dyn Tid<'a>`, so you can't store a value with short lifetime
+/// and get it back as a value with a longer one.
+///
+/// ```rust
+/// # use better_any::{tid, map::TidMap};
+/// struct Name<'a>(&'a str);
+/// tid!(Name<'a>);
+///
+/// let s = String::from("name");
+/// let mut map = TidMap::new();
+/// map.insert(Name(&s));
+/// map.insert(5usize);
+/// assert_eq!(map.get::<Name>().unwrap().0, "name");
+/// assert_eq!(map.remove::<usize>(), Some(5));
+/// assert!(map.get::<usize>().is_none());
+/// ```
+///
+/// ```rust,compile_fail
+/// # use better_any::map::TidMap;
+/// fn shorten<'a: 'b, 'b>(map: TidMap<'a>) -> TidMap<'b> {
+///     map
+/// }
+/// ```
+///
+/// Same for its iterators:
+/// ```rust,compile_fail
+/// # use better_any::map::IterMut;
+/// fn shorten<'m, 'a: 'b, 'b>(iter: IterMut<'m, 'a>) -> IterMut<'m, 'b> {
+///     iter
+/// }
+/// ```
+#[derive(Default)]
+pub struct TidMap<'a> {
+    map: HashMap<TypeId, Box<dyn Tid<'a> + 'a>>,
+}
+
+impl<'a> TidMap<'a> {
+    /// Creates an empty map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts `value` into the map, returns previous value of the same type if any
+    pub fn insert<T: Tid<'a>>(&mut self, value: T) -> Option<T> {
+        self.map
+            .insert(T::id(), Box::new(value))
+            .map(|old| *into_typed(old))
+    }
+
+    /// Returns reference to the value of type `T` if any
+    pub fn get<T: Tid<'a>>(&self) -> Option<&T> {
+        self.map.get(&T::id()).and_then(|it| (**it).downcast_ref())
+    }
+
+    /// Returns mutable reference to the value of type `T` if any
+    pub fn get_mut<T: Tid<'a>>(&mut self) -> Option<&mut T> {
+        self.map
+            .get_mut(&T::id())
+            .and_then(|it| (**it).downcast_mut())
+    }
+
+    /// Removes value of type `T` from the map
+    pub fn remove<T: Tid<'a>>(&mut self) -> Option<T> {
+        self.map.remove(&T::id()).map(|it| *into_typed(it))
+    }
+
+    /// Gets entry of type `T` for in-place manipulation
+    ///
+    /// ```rust
+    /// # use better_any::map::TidMap;
+    /// let mut map = TidMap::new();
+    /// *map.entry::<usize>().or_insert(1) += 1;
+    /// *map.entry::<usize>().or_insert(1) += 1;
+    /// assert_eq!(map.get::<usize>(), Some(&3));
+    /// ```
+    pub fn entry<T: Tid<'a>>(&mut self) -> Entry<'_, 'a, T> {
+        Entry {
+            entry: self.map.entry(T::id()),
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns number of values in the map
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map contains no values
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Removes all values from the map
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+
+    /// Iterates over values in arbitrary order
+    ///
+    /// ```rust
+    /// # use better_any::map::TidMap;
+    /// # use better_any::TidExt;
+    /// let mut map = TidMap::new();
+    /// map.insert(5usize);
+    /// map.insert(String::from("value"));
+    /// assert_eq!(map.iter().count(), 2);
+    /// assert!(map.iter().any(|it| it.is::<usize>()));
+    /// assert!(map.iter().any(|it| it.is::<String>()));
+    /// ```
+    pub fn iter(&self) -> Iter<'_, 'a> {
+        Iter {
+            iter: self.map.values(),
+        }
+    }
+
+    /// Iterates over mutable references to values in arbitrary order
+    pub fn iter_mut(&mut self) -> IterMut<'_, 'a> {
+        IterMut {
+            iter: self.map.values_mut(),
+        }
+    }
+}
+
+/// View into a single entry of [`TidMap`], created by [`TidMap::entry`]
+pub struct Entry<'m, 'a, T> {
+    entry: hash_map::Entry<'m, TypeId, Box<dyn Tid<'a> + 'a>>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<'m, 'a, T: Tid<'a>> Entry<'m, 'a, T> {
+    /// Inserts `default` if entry is empty, returns mutable reference to the value
+    pub fn or_insert(self, default: T) -> &'m mut T {
+        self.or_insert_with(|| default)
+    }
+
+    /// Inserts result of `default` if entry is empty, returns mutable reference to the value
+    pub fn or_insert_with<F: FnOnce() -> T>(self, default: F) -> &'m mut T {
+        let value = self.entry.or_insert_with(|| Box::new(default()));
+        (**value)
+            .downcast_mut()
+            .unwrap_or_else(|| unreachable!("value is stored under wrong type id"))
+    }
+
+    /// Inserts default value of `T` if entry is empty, returns mutable reference to the value
+    pub fn or_default(self) -> &'m mut T
+    where
+        T: Default,
+    {
+        self.or_insert_with(T::default)
+    }
+}
+
+/// Iterator over values of [`TidMap`]
+pub struct Iter<'m, 'a> {
+    iter: hash_map::Values<'m, TypeId, Box<dyn Tid<'a> + 'a>>,
+}
+
+impl<'m, 'a> Iterator for Iter<'m, 'a> {
+    type Item = &'m (dyn Tid<'a> + 'a);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|it| &**it)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// Iterator over mutable references to values of [`TidMap`]
+pub struct IterMut<'m, 'a> {
+    iter: hash_map::ValuesMut<'m, TypeId, Box<dyn Tid<'a> + 'a>>,
+}
+
+impl<'m, 'a> Iterator for IterMut<'m, 'a> {
+    type Item = &'m mut (dyn Tid<'a> + 'a);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|it| &mut **it)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// Owning iterator over values of [`TidMap`]
+pub struct IntoIter<'a> {
+    iter: hash_map::IntoValues<TypeId, Box<dyn Tid<'a> + 'a>>,
+}
+
+impl<'a> Iterator for IntoIter<'a> {
+    type Item = Box<dyn Tid<'a> + 'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a> IntoIterator for TidMap<'a> {
+    type Item = Box<dyn Tid<'a> + 'a>;
+    type IntoIter = IntoIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            iter: self.map.into_values(),
+        }
+    }
+}
+
+impl<'m, 'a> IntoIterator for &'m TidMap<'a> {
+    type Item = &'m (dyn Tid<'a> + 'a);
+    type IntoIter = Iter<'m, 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'m, 'a> IntoIterator for &'m mut TidMap<'a> {
+    type Item = &'m mut (dyn Tid<'a> + 'a);
+    type IntoIter = IterMut<'m, 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+// values are always stored under their own type id
+fn into_typed<'a, T: Tid<'a>>(value: Box<dyn Tid<'a> + 'a>) -> Box<T> {
+    value
+        .downcast_box()
+        .unwrap_or_else(|_| unreachable!("value is stored under wrong type id"))
+}
+
+/// Map of shared thread safe values, at most one of each type
+///
+/// Values are stored as `Arc<dyn Tid + Send + Sync>`, so map can be shared between threads,
+/// but the map itself is not synchronized.
+///
+/// ```rust
+/// # use better_any::map::ArcTidMap;
+/// # use std::sync::Arc;
+/// let mut map = ArcTidMap::new();
+/// map.insert_arc(Arc::new(5usize));
+/// let value: Arc<usize> = map.get().unwrap();
+/// assert_eq!(*value, 5);
+/// assert!(map.get::<u8>().is_none());
+/// ```
+#[derive(Default, Clone)]
+pub struct ArcTidMap<'a> {
+    map: HashMap<TypeId, Arc<dyn Tid<'a> + Send + Sync + 'a>>,
+}
+
+impl<'a> ArcTidMap<'a> {
+    /// Creates an empty map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts `value` into the map, returns previous value of the same type if any
+    pub fn insert_arc<T: Tid<'a> + Send + Sync>(&mut self, value: Arc<T>) -> Option<Arc<T>> {
+        self.map.insert(T::id(), value).map(into_typed_arc)
+    }
+
+    /// Returns shared pointer to the value of type `T` if any
+    pub fn get<T: Tid<'a> + Send + Sync>(&self) -> Option<Arc<T>> {
+        self.map.get(&T::id()).cloned().map(into_typed_arc)
+    }
+
+    /// Removes value of type `T` from the map
+    pub fn remove<T: Tid<'a> + Send + Sync>(&mut self) -> Option<Arc<T>> {
+        self.map.remove(&T::id()).map(into_typed_arc)
+    }
+
+    /// Returns number of values in the map
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map contains no values
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+fn into_typed_arc<'a, T: Tid<'a>>(value: Arc<dyn Tid<'a> + Send + Sync + 'a>) -> Arc<T> {
+    value
+        .downcast_arc()
+        .unwrap_or_else(|_| unreachable!("value is stored under wrong type id"))
+}
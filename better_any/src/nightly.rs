@@ -1,11 +1,13 @@
-use crate::Tid;
-use std::any::Any;
-use std::ops::CoerceUnsized;
-use std::ptr::{DynMetadata, Pointee};
-use std::rc::Rc;
-use std::sync::Arc;
-
-// todo support allocator for heap types
+use crate::{typeid_of, Tid, TidAble};
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::sync::Arc;
+use core::alloc::{Allocator, Layout};
+use core::any::{Any, TypeId};
+use core::ops::{CoerceUnsized, Deref};
+use core::pin::Pin;
+use core::ptr::{DynMetadata, NonNull, Pointee};
+
 /// Implemented for types that can be converted to and from raw painter
 pub trait IntoRawPtr {
     /// Contains lifetime of type if any.
@@ -13,61 +15,69 @@ pub trait IntoRawPtr {
     type Lifetime;
     /// Target of our pointer-like type
     type Pointee: ?Sized;
+    /// Allocator of heap types, `()` for types that don't own their pointee.
+    /// Downcast pointer has the same allocator as the input one.
+    type Allocator;
 
-    /// Converts to raw pointer
-    unsafe fn into_raw(self) -> *const Self::Pointee;
-    /// Reconstruct Self from raw pointer
-    unsafe fn from_raw(from: *const Self::Pointee) -> Self;
+    /// Converts to raw pointer and allocator
+    unsafe fn into_raw(self) -> (*const Self::Pointee, Self::Allocator);
+    /// Reconstruct Self from raw pointer and allocator
+    unsafe fn from_raw(from: *const Self::Pointee, alloc: Self::Allocator) -> Self;
 }
 
-impl<T: ?Sized> IntoRawPtr for Box<T> {
+impl<T: ?Sized, A: Allocator> IntoRawPtr for Box<T, A> {
     type Lifetime = ();
     type Pointee = T;
+    type Allocator = A;
 
-    unsafe fn into_raw(self) -> *const Self::Pointee {
-        Box::into_raw(self)
+    unsafe fn into_raw(self) -> (*const Self::Pointee, A) {
+        let (ptr, alloc) = Box::into_raw_with_allocator(self);
+        (ptr, alloc)
     }
 
-    unsafe fn from_raw(from: *const Self::Pointee) -> Self {
-        Box::from_raw(from as *mut _)
+    unsafe fn from_raw(from: *const Self::Pointee, alloc: A) -> Self {
+        Box::from_raw_in(from as *mut _, alloc)
     }
 }
 
-impl<T: ?Sized> IntoRawPtr for Rc<T> {
+impl<T: ?Sized, A: Allocator> IntoRawPtr for Rc<T, A> {
     type Lifetime = ();
     type Pointee = T;
+    type Allocator = A;
 
-    unsafe fn into_raw(self) -> *const Self::Pointee {
-        Rc::into_raw(self)
+    unsafe fn into_raw(self) -> (*const Self::Pointee, A) {
+        Rc::into_raw_with_allocator(self)
     }
 
-    unsafe fn from_raw(from: *const Self::Pointee) -> Self {
-        Rc::from_raw(from)
+    unsafe fn from_raw(from: *const Self::Pointee, alloc: A) -> Self {
+        Rc::from_raw_in(from, alloc)
     }
 }
 
-impl<T: ?Sized> IntoRawPtr for Arc<T> {
+impl<T: ?Sized, A: Allocator> IntoRawPtr for Arc<T, A> {
     type Lifetime = ();
     type Pointee = T;
+    type Allocator = A;
 
-    unsafe fn into_raw(self) -> *const Self::Pointee {
-        Arc::into_raw(self)
+    unsafe fn into_raw(self) -> (*const Self::Pointee, A) {
+        Arc::into_raw_with_allocator(self)
     }
 
-    unsafe fn from_raw(from: *const Self::Pointee) -> Self {
-        Arc::from_raw(from)
+    unsafe fn from_raw(from: *const Self::Pointee, alloc: A) -> Self {
+        Arc::from_raw_in(from, alloc)
     }
 }
 
 impl<'a, T: ?Sized> IntoRawPtr for &'a T {
     type Lifetime = &'a ();
     type Pointee = T;
+    type Allocator = ();
 
-    unsafe fn into_raw(self) -> *const Self::Pointee {
-        self
+    unsafe fn into_raw(self) -> (*const Self::Pointee, ()) {
+        (self, ())
     }
 
-    unsafe fn from_raw(from: *const Self::Pointee) -> Self {
+    unsafe fn from_raw(from: *const Self::Pointee, _: ()) -> Self {
         &*from
     }
 }
@@ -75,16 +85,101 @@ impl<'a, T: ?Sized> IntoRawPtr for &'a T {
 impl<'a, T: ?Sized> IntoRawPtr for &'a mut T {
     type Lifetime = &'a mut ();
     type Pointee = T;
+    type Allocator = ();
 
-    unsafe fn into_raw(self) -> *const Self::Pointee {
-        self as *mut T as _
+    unsafe fn into_raw(self) -> (*const Self::Pointee, ()) {
+        (self as *mut T as _, ())
     }
 
-    unsafe fn from_raw(from: *const Self::Pointee) -> Self {
+    unsafe fn from_raw(from: *const Self::Pointee, _: ()) -> Self {
         &mut *(from as *mut _)
     }
 }
 
+impl<T: ?Sized> IntoRawPtr for *const T {
+    type Lifetime = *const ();
+    type Pointee = T;
+    type Allocator = ();
+
+    unsafe fn into_raw(self) -> (*const Self::Pointee, ()) {
+        (self, ())
+    }
+
+    unsafe fn from_raw(from: *const Self::Pointee, _: ()) -> Self {
+        from
+    }
+}
+
+impl<T: ?Sized> IntoRawPtr for *mut T {
+    type Lifetime = *mut ();
+    type Pointee = T;
+    type Allocator = ();
+
+    unsafe fn into_raw(self) -> (*const Self::Pointee, ()) {
+        (self, ())
+    }
+
+    unsafe fn from_raw(from: *const Self::Pointee, _: ()) -> Self {
+        from as *mut _
+    }
+}
+
+/// Raw pointers and `NonNull` have distinct `Lifetime` markers,
+/// so possibly null raw pointer can't be downcasted into `NonNull`.
+///
+/// ```compile_fail
+/// # use better_any::nightly::DowncastExt;
+/// # use better_any::{tid, Tid};
+/// # use std::ptr::NonNull;
+/// struct S;
+/// tid!(S);
+/// let raw = core::ptr::null::<S>() as *const dyn Tid;
+/// let _: NonNull<S> = raw.downcast_tid().ok().unwrap();
+/// ```
+impl<T: ?Sized> IntoRawPtr for NonNull<T> {
+    type Lifetime = NonNull<()>;
+    type Pointee = T;
+    type Allocator = ();
+
+    unsafe fn into_raw(self) -> (*const Self::Pointee, ()) {
+        (self.as_ptr(), ())
+    }
+
+    unsafe fn from_raw(from: *const Self::Pointee, _: ()) -> Self {
+        // pointer always comes from `NonNull::into_raw` because of distinct `Lifetime`, so it is not null
+        NonNull::new_unchecked(from as *mut _)
+    }
+}
+
+/// Allows to downcast `Pin<Box<dyn Tid>>` to `Pin<Box<Concrete>>` and similar.
+///
+/// Unpinning in `into_raw` is sound because `Lifetime` of `Pin<P>` differs from the one of `P`,
+/// so the pointer can only be reconstructed as `Pin` again, either as a downcast or as the original one,
+/// and pointee is never moved in between.
+///
+/// ```compile_fail
+/// # use better_any::nightly::DowncastExt;
+/// # use better_any::{tid, Tid};
+/// # use std::pin::Pin;
+/// struct S;
+/// tid!(S);
+/// let pinned = Box::pin(S) as Pin<Box<dyn Tid>>;
+/// let _: Box<S> = pinned.downcast_tid().ok().unwrap();
+/// ```
+impl<P: IntoRawPtr + Deref> IntoRawPtr for Pin<P> {
+    type Lifetime = Pin<P::Lifetime>;
+    type Pointee = P::Pointee;
+    type Allocator = P::Allocator;
+
+    unsafe fn into_raw(self) -> (*const Self::Pointee, Self::Allocator) {
+        Pin::into_inner_unchecked(self).into_raw()
+    }
+
+    unsafe fn from_raw(from: *const Self::Pointee, alloc: Self::Allocator) -> Self {
+        Pin::new_unchecked(P::from_raw(from, alloc))
+    }
+}
+
 // tid!{impl<'a,X> TidAble<'a> for DynMetaData<X> where X:?Sized}
 
 /// Helper trait to retrieve trait object type
@@ -101,19 +196,59 @@ where
     type Over = T;
 }
 
-fn get_callable_trait_object<T: ?Sized + DynMetadataType>(
-    ptr: *const T,
-) -> *const <T as DynMetadataType>::Over {
-    let metadata = ptr.to_raw_parts().1;
-    //SAFETY: currently there is no validity requirements for fat pointers that make data pointer and
-    // vtable pointer in any way dependent.
-    // Here both of these parts are valid independently which is enough for validity of resulting fat pointer
-    core::ptr::from_raw_parts(&(), metadata)
+// Static storage to call methods of small trait objects without touching actual data
+#[repr(align(64))]
+struct CallBuffer(#[allow(dead_code)] [u8; 256]);
+static CALL_BUFFER: CallBuffer = CallBuffer([0; 256]);
+
+fn metadata_of<T: ?Sized + DynMetadataType>(ptr: *const T) -> DynMetadata<T::Over> {
+    ptr.to_raw_parts().1
+}
+
+// Calls `f` with trait object with vtable from `metadata` and dummy data part.
+// Data part has to be dereferenceable and aligned for the concrete type behind `metadata`,
+// otherwise even creation of the reference is UB, so it is either static buffer or,
+// for types larger than 256 bytes or aligned to more than 64, temporary allocation of the type's layout.
+// `f` must not access data behind the reference.
+fn with_vtable_only<T, R>(metadata: DynMetadata<T>, f: impl FnOnce(&T) -> R) -> R
+where
+    T: ?Sized + Pointee<Metadata = DynMetadata<T>>,
+{
+    let layout = metadata.layout();
+    let (data, allocated) = if layout.size() == 0 {
+        (core::ptr::without_provenance::<u8>(layout.align()), false)
+    } else if layout.size() <= core::mem::size_of::<CallBuffer>()
+        && layout.align() <= core::mem::align_of::<CallBuffer>()
+    {
+        (&CALL_BUFFER as *const CallBuffer as *const u8, false)
+    } else {
+        // SAFETY: layout has non zero size
+        let data = unsafe { alloc::alloc::alloc(layout) };
+        if data.is_null() {
+            alloc::alloc::handle_alloc_error(layout)
+        }
+        (data as *const u8, true)
+    };
+    let ptr = core::ptr::from_raw_parts::<T>(data as *const (), metadata);
+    // catch changes in fat pointer layout that would make vtable only pointer call wrong methods,
+    // layout here is read through the vtable of rebuilt pointer
+    debug_assert!(unsafe { Layout::for_value_raw(ptr) } == layout);
+    // SAFETY: data is dereferenceable and aligned for the size and alignment of the concrete type
+    let result = f(unsafe { &*ptr });
+    if allocated {
+        // SAFETY: allocated above with the same layout
+        unsafe { alloc::alloc::dealloc(data as *mut u8, layout) }
+    }
+    result
 }
 
 /// Downcasts any kind of fat pointer type which vtable corresponds to a trait with `Tid` bound.
 /// For example `Rc<RefCell<dyn Tid<'_>>>>` can be downcasted with this method
 ///
+/// Type ids are read from vtables without touching the pointee, so the pointer may even be dangling.
+/// If concrete type is larger than 256 bytes or aligned to more than 64,
+/// it does a temporary heap allocation of the same size on each call.
+///
 /// ```rust
 /// # use better_any::nightly::{downcast_tid, DowncastExt};
 /// # use better_any::{Tid,tid};
@@ -125,7 +260,11 @@ fn get_callable_trait_object<T: ?Sized + DynMetadataType>(
 /// let result: Box<Test> = downcast_tid(any).unwrap_or_else(|_| panic!("error"));
 /// assert_eq!(5, result.0);
 ///```
-pub fn downcast_tid<'a, From: IntoRawPtr, To: IntoRawPtr<Lifetime = From::Lifetime>>(
+pub fn downcast_tid<
+    'a,
+    From: IntoRawPtr,
+    To: IntoRawPtr<Lifetime = From::Lifetime, Allocator = From::Allocator>,
+>(
     f: From,
 ) -> Result<To, From>
 where
@@ -135,26 +274,31 @@ where
     *const To::Pointee: CoerceUnsized<*const From::Pointee>,
     <From::Pointee as DynMetadataType>::Over: Tid<'a>,
 {
-    let raw = unsafe { f.into_raw() };
+    let (raw, alloc) = unsafe { f.into_raw() };
 
-    // get callable vtable for input type
-    let vtable_only_pointer_from = unsafe { &*get_callable_trait_object(raw) };
-    // get callable vtable for output type
-    let vtable_only_pointer_to = unsafe {
-        &*get_callable_trait_object(&() as *const () as *const To::Pointee as *const From::Pointee)
-    };
+    // get vtable for output type, pointer is never dereferenced
+    let to = core::ptr::NonNull::<To::Pointee>::dangling().as_ptr() as *const To::Pointee
+        as *const From::Pointee;
 
     // self_id call does not access `&self`
-    if vtable_only_pointer_from.self_id() == vtable_only_pointer_to.self_id() {
-        unsafe { Ok(To::from_raw(raw as _)) }
+    let from_id = with_vtable_only(metadata_of(raw), |it| it.self_id());
+    let to_id = with_vtable_only(metadata_of(to), |it| it.self_id());
+    // same vtable means same type, so `self_id` called through both rebuilt pointers must agree
+    debug_assert!(metadata_of(raw) != metadata_of(to) || from_id == to_id);
+    if from_id == to_id {
+        // same id means same concrete type behind both vtables
+        debug_assert!(metadata_of(raw).layout() == metadata_of(to).layout());
+        unsafe { Ok(To::from_raw(raw as _, alloc)) }
     } else {
-        Err(unsafe { From::from_raw(raw) })
+        Err(unsafe { From::from_raw(raw, alloc) })
     }
 }
 
 /// Downcasts any kind of fat pointer type which vtable corresponds to a trait with `Any` bound.
 /// For example `Rc<RefCell<dyn Any>>>` can be downcasted with this method
 ///
+/// Has the same allocation behavior as [`downcast_tid`].
+///
 ///```rust
 /// # use better_any::nightly::{downcast_any, DowncastExt};
 /// # use std::any::Any;
@@ -165,7 +309,10 @@ where
 /// assert_eq!(a, *result);
 /// assert!(downcast_any::<_, &usize>(any).is_err());
 ///```
-pub fn downcast_any<From: IntoRawPtr, To: IntoRawPtr<Lifetime = From::Lifetime>>(
+pub fn downcast_any<
+    From: IntoRawPtr,
+    To: IntoRawPtr<Lifetime = From::Lifetime, Allocator = From::Allocator>,
+>(
     f: From,
 ) -> Result<To, From>
 where
@@ -175,20 +322,23 @@ where
     *const To::Pointee: CoerceUnsized<*const From::Pointee>,
     <From::Pointee as DynMetadataType>::Over: Any,
 {
-    let raw = unsafe { f.into_raw() };
+    let (raw, alloc) = unsafe { f.into_raw() };
 
-    // get callable vtable for input type
-    let vtable_only_pointer_from = unsafe { &*get_callable_trait_object(raw) };
-    // get callable vtable for output type
-    let vtable_only_pointer_to = unsafe {
-        &*get_callable_trait_object(&() as *const () as *const To::Pointee as *const From::Pointee)
-    };
+    // get vtable for output type, pointer is never dereferenced
+    let to = core::ptr::NonNull::<To::Pointee>::dangling().as_ptr() as *const To::Pointee
+        as *const From::Pointee;
 
-    // self_id call does not access `&self`
-    if vtable_only_pointer_from.type_id() == vtable_only_pointer_to.type_id() {
-        unsafe { Ok(To::from_raw(raw as _)) }
+    // type_id call does not access `&self`
+    let from_id = with_vtable_only(metadata_of(raw), |it| it.type_id());
+    let to_id = with_vtable_only(metadata_of(to), |it| it.type_id());
+    // same vtable means same type, so `type_id` called through both rebuilt pointers must agree
+    debug_assert!(metadata_of(raw) != metadata_of(to) || from_id == to_id);
+    if from_id == to_id {
+        // same id means same concrete type behind both vtables
+        debug_assert!(metadata_of(raw).layout() == metadata_of(to).layout());
+        unsafe { Ok(To::from_raw(raw as _, alloc)) }
     } else {
-        Err(unsafe { From::from_raw(raw) })
+        Err(unsafe { From::from_raw(raw, alloc) })
     }
 }
 
@@ -219,7 +369,7 @@ pub trait DowncastExt: Sized + IntoRawPtr {
     fn downcast_any<T>(self) -> Result<T, Self>
     where
         Self::Pointee: Pointee + DynMetadataType,
-        T: IntoRawPtr<Lifetime = Self::Lifetime>,
+        T: IntoRawPtr<Lifetime = Self::Lifetime, Allocator = Self::Allocator>,
         T::Pointee: Sized,
         *const T::Pointee: CoerceUnsized<*const Self::Pointee>,
         <Self::Pointee as DynMetadataType>::Over: Any,
@@ -231,7 +381,7 @@ pub trait DowncastExt: Sized + IntoRawPtr {
     fn downcast_tid<'a, T: IntoRawPtr>(self) -> Result<T, Self>
     where
         Self::Pointee: Pointee + DynMetadataType,
-        T: IntoRawPtr<Lifetime = Self::Lifetime>,
+        T: IntoRawPtr<Lifetime = Self::Lifetime, Allocator = Self::Allocator>,
         T::Pointee: Sized,
         *const T::Pointee: CoerceUnsized<*const Self::Pointee>,
         <Self::Pointee as DynMetadataType>::Over: Tid<'a>,
@@ -242,6 +392,72 @@ pub trait DowncastExt: Sized + IntoRawPtr {
 
 impl<T: IntoRawPtr> DowncastExt for T where T::Pointee: DynMetadataType {}
 
+/// Compile time identifier of `Tid` types, computed as a hash of `core::any::type_name`
+///
+/// Can be used in `match` arms and `const` items, for example to build static dispatch tables.
+///
+/// It is not a replacement for `Tid::self_id`, never rely on it for soundness:
+/// type names are not guaranteed to be unique or stable across compiler versions,
+/// and even different names can have the same hash.
+/// Use [`ConstIdChecker`] to check at runtime that used types don't collide.
+///
+/// ```rust
+/// # use better_any::nightly::ConstId;
+/// # use better_any::tid;
+/// struct S;
+/// tid!(S);
+/// const IDS: [u64; 2] = [S::CONST_ID, <usize as ConstId>::CONST_ID];
+/// assert_ne!(IDS[0], IDS[1]);
+/// ```
+pub trait ConstId<'a>: TidAble<'a> {
+    /// Hash of the type name
+    const CONST_ID: u64;
+}
+
+impl<'a, T: ?Sized + TidAble<'a>> ConstId<'a> for T {
+    const CONST_ID: u64 = fnv1a(core::any::type_name::<T>().as_bytes());
+}
+
+const fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+        i += 1;
+    }
+    hash
+}
+
+/// Checks that `ConstId::CONST_ID` of checked types agree with their type ids
+///
+/// ```rust
+/// # use better_any::nightly::ConstIdChecker;
+/// let mut checker = ConstIdChecker::new();
+/// assert!(checker.check::<u8>());
+/// assert!(checker.check::<u16>());
+/// assert!(checker.check::<u8>());
+/// ```
+#[cfg(feature = "std")]
+#[derive(Default, Debug)]
+pub struct ConstIdChecker {
+    ids: std::collections::HashMap<u64, TypeId>,
+}
+
+#[cfg(feature = "std")]
+impl ConstIdChecker {
+    /// Creates checker without recorded types
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records `T`, returns `false` if some other type with the same `CONST_ID` has been recorded before
+    pub fn check<'a, T: ?Sized + ConstId<'a>>(&mut self) -> bool {
+        let id = typeid_of::<T>();
+        *self.ids.entry(T::CONST_ID).or_insert(id) == id
+    }
+}
+
 /// Checks that wrong lifetime doesn't work
 /// ```rust,compile_fail
 /// # use better_any::nightly::{downcast_any, DowncastExt};
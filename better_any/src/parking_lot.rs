@@ -0,0 +1,56 @@
+use crate::{Tid, TidExt};
+use parking_lot::{
+    MappedMutexGuard, MappedRwLockReadGuard, MappedRwLockWriteGuard, Mutex, MutexGuard, RwLock,
+    RwLockReadGuard, RwLockWriteGuard,
+};
+
+tid!(impl<'a, T> TidAble<'a> for Mutex<T> where T:?Sized);
+tid!(impl<'a, T> TidAble<'a> for RwLock<T> where T:?Sized);
+
+/// Lock guard that can be downcast to a guard of concrete type
+///
+/// ```rust
+/// # use better_any::{tid, Tid};
+/// # use better_any::parking_lot::TidGuard;
+/// # use parking_lot::Mutex;
+/// struct S(usize);
+/// tid!(S);
+///
+/// let lock: Box<Mutex<dyn Tid>> = Box::new(Mutex::new(S(5)));
+/// let guard = lock.lock().downcast_guard::<usize>().err().unwrap();
+/// let mut guard = guard.downcast_guard::<S>().ok().unwrap();
+/// guard.0 += 1;
+/// drop(guard);
+/// assert!(lock.try_lock().is_some());
+/// ```
+pub trait TidGuard<'a>: Sized {
+    /// Guard of the value of concrete type `T`
+    type Mapped<T: Tid<'a>>;
+
+    /// Attempts to downcast guarded value to `T`, returns original guard on failure
+    fn downcast_guard<T: Tid<'a>>(self) -> Result<Self::Mapped<T>, Self>;
+}
+
+impl<'g, 'a: 'g, X: ?Sized + Tid<'a>> TidGuard<'a> for MutexGuard<'g, X> {
+    type Mapped<T: Tid<'a>> = MappedMutexGuard<'g, T>;
+
+    fn downcast_guard<T: Tid<'a>>(self) -> Result<Self::Mapped<T>, Self> {
+        MutexGuard::try_map(self, |it| it.downcast_mut::<T>())
+    }
+}
+
+impl<'g, 'a: 'g, X: ?Sized + Tid<'a>> TidGuard<'a> for RwLockReadGuard<'g, X> {
+    type Mapped<T: Tid<'a>> = MappedRwLockReadGuard<'g, T>;
+
+    fn downcast_guard<T: Tid<'a>>(self) -> Result<Self::Mapped<T>, Self> {
+        RwLockReadGuard::try_map(self, |it| it.downcast_ref::<T>())
+    }
+}
+
+impl<'g, 'a: 'g, X: ?Sized + Tid<'a>> TidGuard<'a> for RwLockWriteGuard<'g, X> {
+    type Mapped<T: Tid<'a>> = MappedRwLockWriteGuard<'g, T>;
+
+    fn downcast_guard<T: Tid<'a>>(self) -> Result<Self::Mapped<T>, Self> {
+        RwLockWriteGuard::try_map(self, |it| it.downcast_mut::<T>())
+    }
+}
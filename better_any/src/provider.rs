@@ -1,16 +1,203 @@
-use crate::Tid;
-
+use crate::{Tid, TidExt};
+use alloc::boxed::Box;
+use core::any::TypeId;
 
+/// Trait for types that can provide values and references of other types on demand.
+///
+/// It is an analog of unstable `core::any::Provider`, but requested types can be non-static.
+///
+/// ```rust
+/// # use better_any::{tid, Tid};
+/// # use better_any::provider::{Demand, Provider, ProviderExt};
+/// struct Name<'a>(&'a str);
+/// tid!(Name<'a>);
+///
+/// struct Config<'a> {
+///     name: Name<'a>,
+///     retries: usize,
+/// }
+/// tid!(Config<'a>);
+///
+/// impl<'x> Provider<'x> for Config<'x> {
+///     fn provide<'a>(&'a self, demand: &mut Demand<'a, 'x>) {
+///         demand
+///             .provide_ref(&self.name)
+///             .provide_value(|| self.retries);
+///     }
+/// }
+///
+/// let s = String::from("name");
+/// let config = Config { name: Name(&s), retries: 3 };
+/// assert_eq!(config.request_ref::<Name>().unwrap().0, "name");
+/// assert_eq!(config.request_value::<usize>(), Some(3));
+/// assert!(config.request_ref::<usize>().is_none());
+/// ```
 pub trait Provider<'x>: Tid<'x> {
-    fn provide<'a>(&self, client: &mut Demand<'x>);
-    fn provide_mut<'a>(&'a mut self, client: &mut Demand<'a, 'x>);
+    /// Provides data to `demand` via `Demand::provide_*` methods
+    fn provide<'a>(&'a self, demand: &mut Demand<'a, 'x>);
 }
 
-struct TypedOption<'a, 'x, T:Tid<'x>>(Option<T>);
+/// Derive macro to implement `Provider` by providing fields marked with `#[provide]`
+///
+/// Fields marked with `#[provide]` are provided by reference and fields marked with `#[provide(value)]`
+/// are provided by value via `Clone`. `Provider`, `Demand` and `TidAble` must be in scope.
+///
+/// ```rust
+/// # use better_any::{Tid, TidAble};
+/// # use better_any::provider::{Demand, Provider, ProviderExt};
+/// #[derive(Tid)]
+/// struct Name<'a>(&'a str);
+///
+/// #[derive(Tid, Provider)]
+/// struct Config<'a> {
+///     #[provide]
+///     name: Name<'a>,
+///     #[provide(value)]
+///     retries: usize,
+///     ignored: u8,
+/// }
+///
+/// let config = Config { name: Name("name"), retries: 3, ignored: 0 };
+/// assert_eq!(config.request_ref::<Name>().unwrap().0, "name");
+/// assert_eq!(config.request_value::<usize>(), Some(3));
+/// assert!(config.request_ref::<u8>().is_none());
+/// ```
+#[cfg(feature = "derive")]
+pub use better_typeid_derive::Provider;
+
+enum Slot<'a, 'x> {
+    Ref(Option<&'a (dyn Tid<'x> + 'x)>),
+    Value(Option<Box<dyn Tid<'x> + 'x>>),
+}
 
-trait Demander<'a,'x>{
-    fn requests_for(&mut self,)
+/// Request for a reference or a value of a particular type.
+///
+/// Only the first provided reference or value of demanded type is used.
+pub struct Demand<'a, 'x> {
+    id: TypeId,
+    slot: Slot<'a, 'x>,
 }
 
+impl<'a, 'x> Demand<'a, 'x> {
+    fn new_ref<T: Tid<'x>>() -> Self {
+        Demand {
+            id: T::id(),
+            slot: Slot::Ref(None),
+        }
+    }
+
+    fn new_value<T: Tid<'x>>() -> Self {
+        Demand {
+            id: T::id(),
+            slot: Slot::Value(None),
+        }
+    }
+
+    /// Provides reference to `T` if reference to `T` is demanded
+    pub fn provide_ref<T: Tid<'x>>(&mut self, value: &'a T) -> &mut Self {
+        if let Slot::Ref(slot @ None) = &mut self.slot {
+            if self.id == T::id() {
+                *slot = Some(value);
+            }
+        }
+        self
+    }
 
-pub struct Demand<'a: 'x, 'x>(dyn Demander<'a, 'x> + 'a);
+    /// Provides value of type `T` if value of type `T` is demanded.
+    ///
+    /// `f` is called only if value is actually going to be used.
+    pub fn provide_value<T: Tid<'x>>(&mut self, f: impl FnOnce() -> T) -> &mut Self {
+        if let Slot::Value(slot @ None) = &mut self.slot {
+            if self.id == T::id() {
+                *slot = Some(Box::new(f()));
+            }
+        }
+        self
+    }
+
+    /// Delegates demand for a reference to nested `provider` if it is not satisfied yet
+    ///
+    /// Demands for values are not delegated.
+    ///
+    /// ```rust
+    /// # use better_any::{tid, Tid};
+    /// # use better_any::provider::{Demand, Provider, ProviderExt};
+    /// struct Name<'a>(&'a str);
+    /// tid!(Name<'a>);
+    ///
+    /// struct Inner<'a>(Name<'a>);
+    /// tid!(Inner<'a>);
+    /// impl<'x> Provider<'x> for Inner<'x> {
+    ///     fn provide<'a>(&'a self, demand: &mut Demand<'a, 'x>) {
+    ///         demand.provide_ref(&self.0);
+    ///     }
+    /// }
+    ///
+    /// struct Outer<'a>(Inner<'a>);
+    /// tid!(Outer<'a>);
+    /// impl<'x> Provider<'x> for Outer<'x> {
+    ///     fn provide<'a>(&'a self, demand: &mut Demand<'a, 'x>) {
+    ///         demand.provide_tid_ref(&self.0);
+    ///     }
+    /// }
+    ///
+    /// let outer = Outer(Inner(Name("name")));
+    /// assert_eq!(outer.request_ref::<Name>().unwrap().0, "name");
+    /// ```
+    pub fn provide_tid_ref<P: ?Sized + Provider<'x>>(&mut self, provider: &'a P) -> &mut Self {
+        if let Slot::Ref(None) = self.slot {
+            provider.provide(self);
+        }
+        self
+    }
+
+    fn into_ref<T: Tid<'x>>(self) -> Option<&'a T> {
+        match self.slot {
+            Slot::Ref(value) => value.and_then(|it| it.downcast_ref()),
+            Slot::Value(_) => None,
+        }
+    }
+
+    fn into_value<T: Tid<'x>>(self) -> Option<T> {
+        match self.slot {
+            Slot::Value(value) => value.and_then(|it| it.downcast_box().ok()).map(|it| *it),
+            Slot::Ref(_) => None,
+        }
+    }
+}
+
+/// Extension trait with methods to request data from `Provider`
+pub trait ProviderExt<'x>: Provider<'x> {
+    /// Requests reference to `T` from provider
+    fn request_ref<'a, T: Tid<'x>>(&'a self) -> Option<&'a T>
+    where
+        'x: 'a,
+    {
+        let mut demand = Demand::new_ref::<T>();
+        self.provide(&mut demand);
+        demand.into_ref()
+    }
+
+    /// Requests value of type `T` from provider
+    fn request_value<T: Tid<'x>>(&self) -> Option<T> {
+        let mut demand = Demand::new_value::<T>();
+        self.provide(&mut demand);
+        demand.into_value()
+    }
+}
+
+impl<'x, P: ?Sized + Provider<'x>> ProviderExt<'x> for P {}
+
+/// Requests reference to `T` from `provider`
+///
+/// Same as `ProviderExt::request_ref` but for trait objects.
+pub fn request_ref<'a, 'x: 'a, T: Tid<'x>>(provider: &'a (dyn Provider<'x> + 'a)) -> Option<&'a T> {
+    provider.request_ref()
+}
+
+/// Requests value of type `T` from `provider`
+///
+/// Same as `ProviderExt::request_value` but for trait objects.
+pub fn request_value<'x, T: Tid<'x>>(provider: &dyn Provider<'x>) -> Option<T> {
+    provider.request_value()
+}
@@ -0,0 +1,265 @@
+use crate::{any_bridge_id, erased_any_id, typeid_of, Tid, TidAble, TidExt};
+use std::any::TypeId;
+use std::collections::HashMap;
+
+// key under which registries store types
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum Key {
+    // `self_id` of `dyn Tid`
+    Tid(TypeId),
+    // `Any::type_id` of the value erased to `dyn Any` before it became `dyn Tid`
+    Any(TypeId),
+}
+
+impl Key {
+    fn of<'a>(value: &(dyn Tid<'a> + 'a)) -> Self {
+        erased_any_id(value).map_or(Key::Tid(value.self_id()), Key::Any)
+    }
+
+    fn all<T: TidAble<'static>>() -> [Self; 3] {
+        [
+            Key::Tid(typeid_of::<T>()),
+            Key::Tid(any_bridge_id::<T>()),
+            Key::Any(TypeId::of::<T>()),
+        ]
+    }
+}
+
+/// Information about type registered in [`ProvenanceAwareRegistry`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegisteredType {
+    /// Type id of `dyn Tid` created directly from the type
+    pub native_id: TypeId,
+    /// Type id of `dyn Tid` created from the type via `From` implementations for `Any` types
+    pub bridge_id: TypeId,
+    /// Name of the type, for diagnostic purposes only
+    pub name: &'static str,
+}
+
+/// Registry that recognizes registered types regardless of the way `dyn Tid` was created.
+///
+/// Since `dyn Tid` created via `From` for `T: Any` has different type id,
+/// it stores both ids for every registered type.
+/// Values created by [`into_tid_box`](crate::into_tid_box) or from `&dyn Any` all have the same type id,
+/// so they are recognized by `Any::type_id` of the erased value instead.
+/// Only `'static` types can be created via `From`, but registering `T<'static>`
+/// also recognizes `T<'a>` for any `'a` because they have the same id.
+///
+/// ```rust
+/// # use better_any::{tid, Tid};
+/// # use better_any::registry::ProvenanceAwareRegistry;
+/// struct S;
+/// tid!(S);
+///
+/// let mut registry = ProvenanceAwareRegistry::new();
+/// registry.register::<S>();
+/// let direct = registry.lookup(&S as &dyn Tid).unwrap();
+/// let bridged = registry.lookup(<&dyn Tid>::from(&S)).unwrap();
+/// assert_eq!(direct, bridged);
+/// ```
+#[derive(Default, Debug)]
+pub struct ProvenanceAwareRegistry {
+    types: HashMap<Key, RegisteredType>,
+}
+
+impl ProvenanceAwareRegistry {
+    /// Creates empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `T` and returns its information
+    pub fn register<T: TidAble<'static>>(&mut self) -> RegisteredType {
+        let registered = RegisteredType {
+            native_id: typeid_of::<T>(),
+            bridge_id: any_bridge_id::<T>(),
+            name: core::any::type_name::<T>(),
+        };
+        for key in Key::all::<T>() {
+            self.types.insert(key, registered);
+        }
+        registered
+    }
+
+    /// Returns information about type of `value` if it was registered
+    pub fn lookup<'a>(&self, value: &(dyn Tid<'a> + 'a)) -> Option<RegisteredType> {
+        self.types.get(&Key::of(value)).copied()
+    }
+}
+
+/// Registry of human readable names of types, for example for error messages.
+///
+/// Unlike `Tid::type_name` names are chosen by user and don't depend on compiler version.
+/// Name registered for `T` is also returned when `T` was erased via `From` implementations,
+/// including `From<&dyn Any>`, or [`into_tid_box`](crate::into_tid_box),
+/// since the name describes the value, not its wrapper.
+///
+/// ```rust
+/// # use better_any::{tid, Tid};
+/// # use better_any::registry::NameRegistry;
+/// struct S;
+/// tid!(S);
+///
+/// let mut registry = NameRegistry::new();
+/// registry.register::<S>("plugin::S");
+/// assert_eq!(registry.name_of(&S as &dyn Tid), Some("plugin::S"));
+/// assert_eq!(registry.name_of(<&dyn Tid>::from(&S)), Some("plugin::S"));
+/// assert_eq!(registry.name_of(&5usize as &dyn Tid), None);
+/// ```
+#[derive(Default, Debug)]
+pub struct NameRegistry {
+    names: HashMap<Key, &'static str>,
+}
+
+impl NameRegistry {
+    /// Creates empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `name` for `T`, replacing previously registered one if any
+    pub fn register<T: TidAble<'static>>(&mut self, name: &'static str) {
+        for key in Key::all::<T>() {
+            self.names.insert(key, name);
+        }
+    }
+
+    /// Returns name registered for type of `value`
+    pub fn name_of<'a>(&self, value: &(dyn Tid<'a> + 'a)) -> Option<&'static str> {
+        self.names.get(&Key::of(value)).copied()
+    }
+}
+
+/// Registry of capabilities that types declare to have, for dynamic capability queries in plugin systems.
+///
+/// Capability is identified by `typeid_of` of some `TidAble` type, usually a trait object of the corresponding trait.
+/// It is not a reflection, only explicitly registered pairs are known.
+/// Capabilities registered for `T` are reported for any `dyn Tid` that holds `T`,
+/// whether it was created directly, via `From` implementations or with [`into_tid_box`](crate::into_tid_box).
+///
+/// ```rust
+/// # use better_any::{tid, typeid_of, Tid};
+/// # use better_any::registry::CapabilityRegistry;
+/// trait Draw {}
+/// tid! { impl<'a> TidAble<'a> for dyn Draw + 'a }
+///
+/// struct Circle;
+/// tid!(Circle);
+/// impl Draw for Circle {}
+///
+/// let mut registry = CapabilityRegistry::new();
+/// registry.register::<Circle, dyn Draw>();
+/// assert!(registry.has::<dyn Draw>(&Circle as &dyn Tid));
+/// assert_eq!(
+///     registry.supertraits_of(&Circle as &dyn Tid).collect::<Vec<_>>(),
+///     [typeid_of::<dyn Draw>()]
+/// );
+/// ```
+#[derive(Default, Debug)]
+pub struct CapabilityRegistry {
+    capabilities: HashMap<Key, Vec<TypeId>>,
+}
+
+impl CapabilityRegistry {
+    /// Creates empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares that `T` has capability `C`
+    ///
+    /// Registering the same pair again has no effect.
+    pub fn register<T: TidAble<'static>, C: ?Sized + TidAble<'static>>(&mut self) {
+        let capability = typeid_of::<C>();
+        for key in Key::all::<T>() {
+            let capabilities = self.capabilities.entry(key).or_default();
+            if !capabilities.contains(&capability) {
+                capabilities.push(capability);
+            }
+        }
+    }
+
+    /// Returns ids of capabilities registered for the type of `value` in order of registration
+    pub fn supertraits_of<'a>(
+        &self,
+        value: &(dyn Tid<'a> + 'a),
+    ) -> impl Iterator<Item = TypeId> + '_ {
+        self.capabilities
+            .get(&Key::of(value))
+            .into_iter()
+            .flatten()
+            .copied()
+    }
+
+    /// Returns true if capability `C` was registered for the type of `value`
+    pub fn has<'a, C: ?Sized + TidAble<'static>>(&self, value: &(dyn Tid<'a> + 'a)) -> bool {
+        let capability = typeid_of::<C>();
+        self.supertraits_of(value).any(|it| it == capability)
+    }
+}
+
+type Callback<'c, 'a> = &'c mut dyn FnMut(&(dyn Tid<'a> + 'a));
+type Extractor<'a> = Box<dyn Fn(&(dyn Tid<'a> + 'a), Callback<'_, 'a>) + 'a>;
+
+/// Registry of containers that allows to access value inside of container without knowing its exact type.
+///
+/// Rust can't express downcast to `RefCell<X>` for any `X`,
+/// so instead every concrete container type is registered with a function that extracts inner value.
+/// Extractor passes inner value to callback instead of returning it,
+/// so it works for containers like `RefCell` where access is guarded.
+///
+/// ```rust
+/// # use better_any::{tid, Tid, TidExt};
+/// # use better_any::registry::ContainerRegistry;
+/// # use std::cell::RefCell;
+/// struct S(usize);
+/// tid!(S);
+///
+/// let mut registry = ContainerRegistry::new();
+/// registry.register::<RefCell<S>>(|cell, f| f(&*cell.borrow()));
+/// let cell = RefCell::new(S(5));
+/// let inner = registry.downcast_container(&cell, |inner| inner.downcast_ref::<S>().unwrap().0);
+/// assert_eq!(inner, Some(5));
+/// ```
+#[derive(Default)]
+pub struct ContainerRegistry<'a> {
+    extractors: HashMap<TypeId, Extractor<'a>>,
+}
+
+impl<'a> ContainerRegistry<'a> {
+    /// Creates empty registry
+    pub fn new() -> Self {
+        ContainerRegistry {
+            extractors: HashMap::new(),
+        }
+    }
+
+    /// Registers container `C` with function that passes its inner value to provided callback
+    ///
+    /// Replaces previously registered extractor for `C` if any.
+    pub fn register<C: TidAble<'a>>(&mut self, extract: fn(&C, Callback<'_, 'a>)) {
+        self.extractors.insert(
+            typeid_of::<C>(),
+            Box::new(move |value, f| extract(value.downcast_ref::<C>().unwrap(), f)),
+        );
+    }
+
+    /// Calls `f` with the value inside of `container` if type of `container` was registered
+    ///
+    /// Returns `None` if container type is not registered or if extractor didn't provide value.
+    pub fn downcast_container<R>(
+        &self,
+        container: &(dyn Tid<'a> + 'a),
+        f: impl FnOnce(&(dyn Tid<'a> + 'a)) -> R,
+    ) -> Option<R> {
+        let extract = self.extractors.get(&container.self_id())?;
+        let mut f = Some(f);
+        let mut result = None;
+        extract(container, &mut |inner| {
+            if let Some(f) = f.take() {
+                result = Some(f(inner));
+            }
+        });
+        result
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{Tid, TidExt};
+use alloc::boxed::Box;
+use core::any::TypeId;
+use core::fmt;
+use serde::de::{self, DeserializeOwned, DeserializeSeed, MapAccess, Visitor};
+use serde::ser::{self, SerializeMap};
+use serde::{Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+
+type DeserializeFn<'a> = fn(
+    &mut dyn erased_serde::Deserializer<'_>,
+) -> Result<Box<dyn Tid<'a> + 'a>, erased_serde::Error>;
+type SerializeFn<'a> = for<'v> fn(&'v (dyn Tid<'a> + 'a)) -> &'v dyn erased_serde::Serialize;
+
+/// Registry of types that can be serialized and deserialized behind `dyn Tid`
+///
+/// Values are serialized as a map with single entry from registered name of the type to the value itself,
+/// same as externally tagged enums in `serde`.
+/// Only registered types can round-trip, serialization of other types
+/// and deserialization of unknown names fail with an error.
+///
+/// ```rust
+/// # use better_any::{Tid, TidExt};
+/// # use better_any::serialize::TidRegistry;
+/// let mut registry = TidRegistry::new();
+/// registry.register::<Vec<u32>>("points");
+///
+/// let json = serde_json::to_string(&registry.tagged(&vec![1u32, 2])).unwrap();
+/// assert_eq!(json, r#"{"points":[1,2]}"#);
+/// let value = registry.deserialize_tid(&mut serde_json::Deserializer::from_str(&json)).unwrap();
+/// assert_eq!((*value).downcast_ref::<Vec<u32>>().unwrap()[1], 2);
+/// ```
+#[derive(Default)]
+pub struct TidRegistry<'a> {
+    deserializers: HashMap<&'static str, DeserializeFn<'a>>,
+    serializers: HashMap<TypeId, (&'static str, SerializeFn<'a>)>,
+}
+
+fn deserialize<'a, T: Tid<'a> + DeserializeOwned>(
+    deserializer: &mut dyn erased_serde::Deserializer<'_>,
+) -> Result<Box<dyn Tid<'a> + 'a>, erased_serde::Error> {
+    Ok(Box::new(erased_serde::deserialize::<T>(deserializer)?))
+}
+
+fn as_serialize<'v, 'a, T: Tid<'a> + Serialize>(
+    value: &'v (dyn Tid<'a> + 'a),
+) -> &'v dyn erased_serde::Serialize {
+    value.downcast_ref::<T>().unwrap()
+}
+
+impl<'a> TidRegistry<'a> {
+    /// Creates empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `T` under `name`
+    ///
+    /// Replaces previous registration of `T` or of the `name` if any.
+    pub fn register<T: Tid<'a> + DeserializeOwned + Serialize>(&mut self, name: &'static str) {
+        self.deserializers.insert(name, deserialize::<T>);
+        self.serializers.insert(T::id(), (name, as_serialize::<T>));
+    }
+
+    /// Returns name under which type of `value` was registered
+    pub fn name_of(&self, value: &(dyn Tid<'a> + 'a)) -> Option<&'static str> {
+        self.serializers.get(&value.self_id()).map(|it| it.0)
+    }
+
+    /// Wraps `value` into a type that serializes it together with its registered name
+    pub fn tagged<'r>(&'r self, value: &'r (dyn Tid<'a> + 'a)) -> Tagged<'r, 'a> {
+        Tagged {
+            registry: self,
+            value,
+        }
+    }
+
+    /// Serializes `value` together with its registered name
+    pub fn serialize_tid<S: Serializer>(
+        &self,
+        value: &(dyn Tid<'a> + 'a),
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let (name, serialize) = self.serializers.get(&value.self_id()).ok_or_else(|| {
+            ser::Error::custom(format_args!(
+                "type `{}` is not registered",
+                value.type_name()
+            ))
+        })?;
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(name, serialize(value))?;
+        map.end()
+    }
+
+    /// Deserializes value serialized by `serialize_tid` into the registered type
+    pub fn deserialize_tid<'de, D: Deserializer<'de>>(
+        &self,
+        deserializer: D,
+    ) -> Result<Box<dyn Tid<'a> + 'a>, D::Error> {
+        deserializer.deserialize_map(TaggedVisitor(self))
+    }
+}
+
+/// Value together with registry, serializes value with its registered name
+///
+/// Created by [`TidRegistry::tagged`].
+pub struct Tagged<'r, 'a> {
+    registry: &'r TidRegistry<'a>,
+    value: &'r (dyn Tid<'a> + 'a),
+}
+
+impl Serialize for Tagged<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.registry.serialize_tid(self.value, serializer)
+    }
+}
+
+struct TaggedVisitor<'r, 'a>(&'r TidRegistry<'a>);
+
+impl<'de, 'a> Visitor<'de> for TaggedVisitor<'_, 'a> {
+    type Value = Box<dyn Tid<'a> + 'a>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("map with a single entry from registered type name to value")
+    }
+
+    fn visit_map<M: MapAccess<'de>>(self, mut map: M) -> Result<Self::Value, M::Error> {
+        let name = map
+            .next_key::<alloc::string::String>()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let deserialize =
+            self.0.deserializers.get(name.as_str()).ok_or_else(|| {
+                de::Error::custom(format_args!("type `{}` is not registered", name))
+            })?;
+        let value = map.next_value_seed(Seed(*deserialize))?;
+        if map.next_key::<de::IgnoredAny>()?.is_some() {
+            return Err(de::Error::invalid_length(2, &self));
+        }
+        Ok(value)
+    }
+}
+
+struct Seed<'a>(DeserializeFn<'a>);
+
+impl<'de, 'a> DeserializeSeed<'de> for Seed<'a> {
+    type Value = Box<dyn Tid<'a> + 'a>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        let mut erased = <dyn erased_serde::Deserializer<'_>>::erase(deserializer);
+        (self.0)(&mut erased).map_err(de::Error::custom)
+    }
+}
@@ -0,0 +1,11 @@
+use better_any::{Tid, TidAble};
+
+#[derive(Tid)]
+struct Foo<'a, T>(&'a T);
+
+struct NotTid;
+
+fn main() {
+    let value = NotTid;
+    let _ = &Foo(&value) as &dyn Tid;
+}
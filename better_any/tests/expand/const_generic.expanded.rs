@@ -0,0 +1,9 @@
+use better_any::{Tid, TidAble};
+struct Frame<'a, const N: usize>(&'a [u8; N]);
+const _: () = {
+    unsafe impl<'a, const N: usize> TidAble<'a> for Frame<'a, N> {
+        type Static = __TypeIdGenerator<N>;
+    }
+    #[allow(warnings)]
+    pub struct __TypeIdGenerator<const N: usize>();
+};
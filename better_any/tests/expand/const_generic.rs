@@ -0,0 +1,4 @@
+use better_any::{Tid, TidAble};
+
+#[derive(Tid)]
+struct Frame<'a, const N: usize>(&'a [u8; N]);
@@ -0,0 +1,16 @@
+use better_any::{Tid, TidAble};
+use std::marker::PhantomData;
+struct Marker<'a, T> {
+    value: T,
+    marker: PhantomData<&'a u8>,
+}
+const _: () = {
+    unsafe impl<'a, T> TidAble<'a> for Marker<'a, T>
+    where
+        T: TidAble<'a>,
+    {
+        type Static = __TypeIdGenerator<T::Static>;
+    }
+    #[allow(warnings)]
+    pub struct __TypeIdGenerator<T: ?Sized>(core::marker::PhantomData<T>);
+};
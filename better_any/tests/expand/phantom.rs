@@ -0,0 +1,8 @@
+use better_any::{Tid, TidAble};
+use std::marker::PhantomData;
+
+#[derive(Tid)]
+struct Marker<'a, T> {
+    value: T,
+    marker: PhantomData<&'a u8>,
+}
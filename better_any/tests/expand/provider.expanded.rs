@@ -0,0 +1,39 @@
+use better_any::provider::{Demand, Provider};
+use better_any::{Tid, TidAble};
+struct Name<'a>(&'a str);
+const _: () = {
+    unsafe impl<'a> TidAble<'a> for Name<'a> {
+        type Static = __TypeIdGenerator;
+    }
+    #[allow(warnings)]
+    pub struct __TypeIdGenerator();
+};
+struct Request<'a, T> {
+    #[provide]
+    name: Name<'a>,
+    #[provide(value)]
+    body: Option<&'a T>,
+    len: usize,
+}
+const _: () = {
+    unsafe impl<'a, T> TidAble<'a> for Request<'a, T>
+    where
+        T: TidAble<'a>,
+    {
+        type Static = __TypeIdGenerator<T::Static>;
+    }
+    #[allow(warnings)]
+    pub struct __TypeIdGenerator<T: ?Sized>(core::marker::PhantomData<T>);
+};
+impl<'a, T> Provider<'a> for Request<'a, T>
+where
+    Request<'a, T>: TidAble<'a>,
+    Name<'a>: TidAble<'a>,
+    Option<&'a T>: TidAble<'a>,
+    Option<&'a T>: Clone,
+{
+    fn provide<'__provide>(&'__provide self, demand: &mut Demand<'__provide, 'a>) {
+        demand.provide_ref(&self.name);
+        demand.provide_value(|| Clone::clone(&self.body));
+    }
+}
@@ -0,0 +1,14 @@
+use better_any::provider::{Demand, Provider};
+use better_any::{Tid, TidAble};
+
+#[derive(Tid)]
+struct Name<'a>(&'a str);
+
+#[derive(Tid, Provider)]
+struct Request<'a, T> {
+    #[provide]
+    name: Name<'a>,
+    #[provide(value)]
+    body: Option<&'a T>,
+    len: usize,
+}
@@ -0,0 +1,38 @@
+use better_any::tid;
+struct Buffer<'a, const N: usize>(&'a [u8; N]);
+const _: () = {
+    use core::marker::PhantomData;
+    type __Alias<'a, const N: usize> = Buffer<'a, N>;
+    pub struct __TypeIdGenerator<'a, const N: usize>(PhantomData<&'a ()>);
+    unsafe impl<'a, const N: usize> ::better_any::TidAble<'a> for __Alias<'a, N> {
+        type Static = __TypeIdGenerator<'static, N>;
+    }
+};
+struct Grid<'a, T, X: ?Sized, const W: usize, const H: bool>(&'a [[T; W]; 1], Box<X>);
+const _: () = {
+    use core::marker::PhantomData;
+    type __Alias<'a, T, X, const W: usize, const H: bool> = Grid<'a, T, X, W, H>;
+    pub struct __TypeIdGenerator<
+        'a,
+        T: ?Sized,
+        X: ?Sized,
+        const W: usize,
+        const H: bool,
+    >(
+        PhantomData<&'a ()>,
+        PhantomData<T>,
+        PhantomData<X>,
+    );
+    unsafe impl<
+        'a,
+        T: ::better_any::TidAble<'a>,
+        X: 'static,
+        const W: usize,
+        const H: bool,
+    > ::better_any::TidAble<'a> for __Alias<'a, T, X, W, H>
+    where
+        X: ?Sized,
+    {
+        type Static = __TypeIdGenerator<'static, T::Static, X, W, H>;
+    }
+};
@@ -0,0 +1,7 @@
+use better_any::tid;
+
+struct Buffer<'a, const N: usize>(&'a [u8; N]);
+tid! { impl<'a, const N: usize> TidAble<'a> for Buffer<'a, N> }
+
+struct Grid<'a, T, X: ?Sized, const W: usize, const H: bool>(&'a [[T; W]; 1], Box<X>);
+tid! { impl<'a, T, X: 'static, const W: usize, const H: bool> TidAble<'a> for Grid<'a, T, X, W, H> where X: ?Sized }
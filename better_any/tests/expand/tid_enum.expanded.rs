@@ -0,0 +1,21 @@
+use better_any::tid;
+enum Either<'a, L, R> {
+    Left(&'a L),
+    Right(R),
+}
+const _: () = {
+    use core::marker::PhantomData;
+    type __Alias<'a, L, R> = Either<'a, L, R>;
+    pub struct __TypeIdGenerator<'a, L: ?Sized, R: ?Sized>(
+        PhantomData<&'a ()>,
+        PhantomData<L>,
+        PhantomData<R>,
+    );
+    unsafe impl<
+        'a,
+        L: ::better_any::TidAble<'a>,
+        R: ::better_any::TidAble<'a>,
+    > ::better_any::TidAble<'a> for __Alias<'a, L, R> {
+        type Static = __TypeIdGenerator<'static, L::Static, R::Static>;
+    }
+};
@@ -0,0 +1,7 @@
+use better_any::tid;
+
+enum Either<'a, L, R> {
+    Left(&'a L),
+    Right(R),
+}
+tid!(Either<'a, L, R>);
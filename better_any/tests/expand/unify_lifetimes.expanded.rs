@@ -0,0 +1,19 @@
+use better_any::{Tid, TidAble};
+#[tid(unify_lifetimes)]
+struct Pair<'a, 'b: 'a, T>(
+    &'a T,
+    &'b str,
+)
+where
+    T: 'b;
+const _: () = {
+    unsafe impl<'a, T> TidAble<'a> for Pair<'a, 'a, T>
+    where
+        T: 'a,
+        T: TidAble<'a>,
+    {
+        type Static = __TypeIdGenerator<T::Static>;
+    }
+    #[allow(warnings)]
+    pub struct __TypeIdGenerator<T: ?Sized>(core::marker::PhantomData<T>);
+};
@@ -0,0 +1,7 @@
+use better_any::{Tid, TidAble};
+
+#[derive(Tid)]
+#[tid(unify_lifetimes)]
+struct Pair<'a, 'b: 'a, T>(&'a T, &'b str)
+where
+    T: 'b;
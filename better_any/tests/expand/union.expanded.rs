@@ -0,0 +1,16 @@
+use better_any::{Tid, TidAble};
+#[repr(C)]
+union Value<'a, T: Copy> {
+    ptr: &'a T,
+    int: usize,
+}
+const _: () = {
+    unsafe impl<'a, T: Copy> TidAble<'a> for Value<'a, T>
+    where
+        T: TidAble<'a>,
+    {
+        type Static = __TypeIdGenerator<T::Static>;
+    }
+    #[allow(warnings)]
+    pub struct __TypeIdGenerator<T: ?Sized>(core::marker::PhantomData<T>);
+};
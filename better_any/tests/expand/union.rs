@@ -0,0 +1,8 @@
+use better_any::{Tid, TidAble};
+
+#[derive(Tid)]
+#[repr(C)]
+union Value<'a, T: Copy> {
+    ptr: &'a T,
+    int: usize,
+}
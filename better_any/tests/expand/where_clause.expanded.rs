@@ -0,0 +1,41 @@
+use better_any::{Tid, TidAble};
+trait Trait<'a> {
+    type Assoc: Clone;
+}
+struct Wrapper<'a, T>(
+    &'a T,
+)
+where
+    T: Trait<'a>,
+    T::Assoc: Send + 'a;
+const _: () = {
+    unsafe impl<'a, T> TidAble<'a> for Wrapper<'a, T>
+    where
+        T: Trait<'a>,
+        T::Assoc: Send + 'a,
+        T: TidAble<'a>,
+    {
+        type Static = __TypeIdGenerator<T::Static>;
+    }
+    #[allow(warnings)]
+    pub struct __TypeIdGenerator<T: ?Sized>(core::marker::PhantomData<T>);
+};
+struct Items<'a, I>(
+    &'a I,
+    Option<I::Item>,
+)
+where
+    I: Iterator,
+    I::Item: Default;
+const _: () = {
+    unsafe impl<'a, I> TidAble<'a> for Items<'a, I>
+    where
+        I: Iterator,
+        I::Item: Default,
+        I: TidAble<'a>,
+    {
+        type Static = __TypeIdGenerator<I::Static>;
+    }
+    #[allow(warnings)]
+    pub struct __TypeIdGenerator<I: ?Sized>(core::marker::PhantomData<I>);
+};
@@ -0,0 +1,17 @@
+use better_any::{Tid, TidAble};
+
+trait Trait<'a> {
+    type Assoc: Clone;
+}
+
+#[derive(Tid)]
+struct Wrapper<'a, T>(&'a T)
+where
+    T: Trait<'a>,
+    T::Assoc: Send + 'a;
+
+#[derive(Tid)]
+struct Items<'a, I>(&'a I, Option<I::Item>)
+where
+    I: Iterator,
+    I::Item: Default;
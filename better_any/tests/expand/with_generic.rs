@@ -1,5 +1,7 @@
-use better_any::Tid;
-use better_any_derive::Tid;
+use better_any::{Tid, TidAble};
+
+trait Trait {}
+trait TraitLT<'a> {}
 
 #[derive(Tid)]
 struct S3<'a, T>(&'a T);
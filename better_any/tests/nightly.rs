@@ -1,3 +1,6 @@
+#![feature(allocator_api)]
+// Unsafe vtable handling is checked by running these tests under Miri:
+// `cargo +nightly miri test --features nightly --test nightly`
 use better_any::nightly::{downcast_any, DowncastExt};
 use std::any::Any;
 use std::cell::RefCell;
@@ -21,4 +24,174 @@ fn test2() {
     assert_eq!(*a.borrow(), *result.borrow());
 }
 
+#[test]
+fn test_custom_allocator() {
+    use better_any::{tid, Tid};
+    use std::alloc::{AllocError, Allocator, Global, Layout};
+    use std::cell::Cell;
+    use std::ptr::NonNull;
+    use std::rc::Rc;
+
+    #[derive(Clone, Copy)]
+    struct Counting<'c>(&'c Cell<isize>);
+    unsafe impl Allocator for Counting<'_> {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            self.0.set(self.0.get() + 1);
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            self.0.set(self.0.get() - 1);
+            Global.deallocate(ptr, layout)
+        }
+    }
+
+    struct S(usize);
+    tid!(S);
+
+    let live = Cell::new(0);
+    let alloc = Counting(&live);
+    let boxed = Box::new_in(S(5), alloc) as Box<dyn Tid, _>;
+    let boxed = boxed.downcast_tid::<Box<usize, _>>().err().unwrap();
+    let boxed: Box<S, Counting<'_>> = boxed.downcast_tid().ok().unwrap();
+    assert_eq!(boxed.0, 5);
+    assert_eq!(live.get(), 1);
+    drop(boxed);
+    assert_eq!(live.get(), 0);
+
+    let rc = Rc::new_in(S(6), alloc) as Rc<dyn Tid, _>;
+    let rc: Rc<S, Counting<'_>> = rc.downcast_tid().ok().unwrap();
+    assert_eq!(rc.0, 6);
+    drop(rc);
+    assert_eq!(live.get(), 0);
+}
+
 //should fail to compile
+
+#[test]
+fn test_const_id() {
+    use better_any::nightly::{ConstId, ConstIdChecker};
+    use better_any::tid;
+
+    struct Name<'a>(&'a str);
+    tid!(Name<'a>);
+
+    fn describe(id: u64) -> &'static str {
+        match id {
+            <Name<'static> as ConstId>::CONST_ID => "name",
+            <Vec<u8> as ConstId>::CONST_ID => "bytes",
+            _ => "unknown",
+        }
+    }
+
+    let s = String::from("name");
+    fn const_id_of<'a, T: ConstId<'a>>(_: &T) -> u64 {
+        T::CONST_ID
+    }
+    assert_eq!(describe(const_id_of(&Name(&s))), "name");
+    assert_eq!(describe(<Vec<u8>>::CONST_ID), "bytes");
+    assert_eq!(describe(<Vec<u16>>::CONST_ID), "unknown");
+
+    let mut checker = ConstIdChecker::new();
+    assert!(checker.check::<Name>());
+    assert!(checker.check::<Vec<u8>>());
+    assert!(checker.check::<Vec<u16>>());
+    assert!(checker.check::<str>());
+}
+
+#[test]
+fn test_pin() {
+    use better_any::{tid, Tid};
+    use std::pin::Pin;
+
+    struct S(usize);
+    tid!(S);
+
+    let pinned = Box::pin(S(5)) as Pin<Box<dyn Tid>>;
+    let address = &*pinned as *const dyn Tid as *const ();
+    let pinned = pinned.downcast_tid::<Pin<Box<usize>>>().err().unwrap();
+    let pinned: Pin<Box<S>> = pinned.downcast_tid().ok().unwrap();
+    assert_eq!(pinned.0, 5);
+    assert_eq!(&*pinned as *const S as *const (), address);
+}
+
+#[test]
+fn test_raw_pointers() {
+    use better_any::{tid, Tid};
+    use std::ptr::NonNull;
+
+    struct S(usize);
+    tid!(S);
+
+    let mut value = 5usize;
+    let ptr = NonNull::from(&mut value as &mut dyn Any);
+    let ptr = ptr.downcast_any::<NonNull<u8>>().err().unwrap();
+    let ptr: NonNull<usize> = ptr.downcast_any().ok().unwrap();
+    unsafe { *ptr.as_ptr() += 1 };
+    assert_eq!(value, 6);
+
+    let raw = Box::into_raw(Box::new(S(7)) as Box<dyn Tid>);
+    let raw = raw.downcast_tid::<*mut usize>().err().unwrap();
+    let raw: *mut S = raw.downcast_tid().ok().unwrap();
+    let boxed = unsafe { Box::from_raw(raw) };
+    assert_eq!(boxed.0, 7);
+
+    let value = S(8);
+    let raw = &value as &dyn Tid as *const dyn Tid;
+    let raw: *const S = raw.downcast_tid().ok().unwrap();
+    assert_eq!(unsafe { (*raw).0 }, 8);
+}
+
+// also meant to be run under Miri: `cargo +nightly miri test --features nightly --test nightly`
+#[test]
+fn test_vtable_only_pointers() {
+    use better_any::nightly::downcast_tid;
+    use better_any::{tid, Tid};
+    use std::rc::Rc;
+
+    // larger than static call buffer, so vtable is called via temporary allocation
+    #[repr(align(64))]
+    struct Big([u64; 64]);
+    tid!(Big);
+    struct Small(u8);
+    tid!(Small);
+
+    let value = Rc::new(RefCell::new(Big([3; 64]))) as Rc<RefCell<dyn Tid>>;
+    let value = downcast_tid::<_, Rc<RefCell<usize>>>(value).err().unwrap();
+    let value: Rc<RefCell<Big>> = downcast_tid(value).ok().unwrap();
+    assert_eq!(value.borrow().0[63], 3);
+
+    let value = Box::new(Big([4; 64])) as Box<dyn Any>;
+    let value = downcast_any::<_, Box<u8>>(value).err().unwrap();
+    let value: Box<Big> = downcast_any(value).ok().unwrap();
+    assert_eq!(value.0[0], 4);
+
+    let value = Box::new(Small(1)) as Box<dyn Tid>;
+    let value = downcast_tid::<_, Box<Big>>(value).err().unwrap();
+    let value: Box<Small> = downcast_tid(value).ok().unwrap();
+    assert_eq!(value.0, 1);
+}
+
+// under Miri checks that ids are read from vtables without touching dangling data
+#[test]
+fn test_vtable_only_dangling() {
+    use better_any::{tid, Tid};
+    use std::ptr::NonNull;
+
+    struct Unit;
+    tid!(Unit);
+    // aligned above static call buffer, so vtable is called via temporary allocation
+    #[allow(dead_code)]
+    #[repr(align(128))]
+    struct Aligned(u8);
+    tid!(Aligned);
+
+    let dangling = NonNull::<Aligned>::dangling().as_ptr();
+    let raw = dangling as *mut dyn Tid;
+    let raw = raw.downcast_tid::<*mut Unit>().err().unwrap();
+    let raw: *mut Aligned = raw.downcast_tid().ok().unwrap();
+    assert_eq!(raw, dangling);
+
+    let unit = NonNull::<Unit>::dangling().as_ptr() as *mut dyn Tid;
+    assert!(unit.downcast_tid::<*mut Unit>().is_ok());
+}
@@ -0,0 +1,16 @@
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn no_std_build() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let status = Command::new(env!("CARGO"))
+        .arg("build")
+        .arg("--manifest-path")
+        .arg(manifest_dir.join("tests/no_std/Cargo.toml"))
+        .arg("--target-dir")
+        .arg(manifest_dir.join("../target/no_std"))
+        .status()
+        .unwrap();
+    assert!(status.success(), "crate with `#![no_std]` failed to build");
+}
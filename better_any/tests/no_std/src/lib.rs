@@ -0,0 +1,47 @@
+#![no_std]
+extern crate alloc;
+
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use better_any::{tid, tid_enum_dispatch, Tid, TidExt};
+
+pub struct Num(pub usize);
+tid!(Num);
+
+pub struct Name<'a>(pub &'a str);
+tid!(Name<'a>);
+
+pub enum Value<'a> {
+    Num(Num),
+    Name(Name<'a>),
+}
+tid_enum_dispatch!(Value<'a> { Num => Num, Name<'a> => Name });
+
+pub fn name<'a>(value: &'a dyn Tid<'a>) -> Option<&'a str> {
+    value.downcast_ref::<Name<'a>>().map(|it| it.0)
+}
+
+pub fn num(value: Box<dyn Tid<'_> + '_>) -> Option<usize> {
+    value.downcast_box::<Num>().ok().map(|it| it.0)
+}
+
+pub fn cow_len<'a>(value: &'a dyn Tid<'a>) -> Option<usize> {
+    if let Some(text) = value.downcast_ref::<Cow<'a, str>>() {
+        return Some(text.len());
+    }
+    value.downcast_ref::<Cow<'a, [u8]>>().map(|it| it.len())
+}
+
+pub fn into_owned<'a>(value: Box<dyn Tid<'a> + 'a>) -> Option<String> {
+    let text = value.downcast_box::<Cow<'a, str>>().ok()?;
+    Some(text.into_owned())
+}
+
+pub fn cows<'a>(text: &'a str) -> Vec<Box<dyn Tid<'a> + 'a>> {
+    let mut values: Vec<Box<dyn Tid<'a> + 'a>> = Vec::new();
+    values.push(Box::new(Cow::Borrowed(text)));
+    values.push(Box::new(Cow::<[u8]>::Owned(Vec::from(text.as_bytes()))));
+    values
+}
@@ -2,7 +2,7 @@ use crate::mod2::S7;
 use better_any::{impl_tid, tid, type_id, Tid, TidAble, TidExt};
 use std::any::Any;
 
-#[derive(Tid)]
+#[derive(Tid, PartialEq, Eq, PartialOrd, Ord)]
 struct S1(usize);
 
 #[derive(Tid)]
@@ -128,3 +128,2216 @@ fn test_generic_context() {
     assert_eq!(generic(S1(5)).as_ref(), "5");
     assert_eq!(generic(S2("x")).as_ref(), "x");
 }
+
+#[test]
+fn test_downcast_weak() {
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    let s = String::from("weak");
+    let rc = Rc::new(S2(&s)) as Rc<dyn Tid>;
+    let weak = Rc::downgrade(&rc);
+    let weak = TidExt::downcast_weak_rc::<S1>(weak).unwrap_err();
+    let weak = TidExt::downcast_weak_rc::<S2>(weak).unwrap_or_else(|_| panic!());
+    assert_eq!(weak.upgrade().unwrap().0, "weak");
+    let weak = Rc::downgrade(&rc);
+    drop(rc);
+    assert!(TidExt::downcast_weak_rc::<S2>(weak).is_err());
+
+    let arc = Arc::new(S1(5)) as Arc<dyn Tid>;
+    let weak = TidExt::downcast_weak_arc::<S1>(Arc::downgrade(&arc)).unwrap_or_else(|_| panic!());
+    assert_eq!(weak.upgrade().unwrap().0, 5);
+    drop(arc);
+    let dangling = TidExt::downcast_weak_arc::<S1>(weak).unwrap_err();
+    assert!(dangling.upgrade().is_none());
+}
+
+#[test]
+fn test_downcast_pin_box() {
+    let pinned = Box::pin(S1(5)) as std::pin::Pin<Box<dyn Tid>>;
+    let address = &*pinned as *const dyn Tid as *const ();
+    let pinned = pinned.downcast_pin_box::<S2>().err().unwrap();
+    let pinned = pinned.downcast_pin_box::<S1>().unwrap_or_else(|_| panic!());
+    assert_eq!(&*pinned as *const S1 as *const (), address);
+    assert_eq!(pinned.0, 5);
+}
+
+#[test]
+fn test_downcast_hashset_contains() {
+    use std::collections::HashSet;
+
+    #[derive(Tid, Hash, Eq, PartialEq)]
+    struct Key<'a>(&'a str);
+
+    let s = String::from("key");
+    let set = vec![Key(&s)].into_iter().collect::<HashSet<_>>();
+    let erased = &set as &dyn Tid;
+    assert_eq!(erased.downcast_hashset_contains(&Key("key")), Some(true));
+    assert_eq!(erased.downcast_hashset_contains(&Key("other")), Some(false));
+    let not_a_set = &S1(1) as &dyn Tid;
+    assert_eq!(not_a_set.downcast_hashset_contains(&Key("key")), None);
+}
+
+#[test]
+fn test_cow() {
+    use std::borrow::Cow;
+
+    let s = String::from("borrowed");
+    let borrowed: Cow<'_, str> = Cow::Borrowed(&s);
+    let owned: Cow<'_, str> = Cow::Owned(String::from("owned"));
+    let bytes: Cow<'_, [u8]> = Cow::Borrowed(s.as_bytes());
+
+    let erased = [&borrowed as &dyn Tid, &owned, &bytes];
+    assert_eq!(erased[0].downcast_ref::<Cow<str>>().unwrap(), "borrowed");
+    assert_eq!(erased[1].downcast_ref::<Cow<str>>().unwrap(), "owned");
+    assert!(erased[2].downcast_ref::<Cow<str>>().is_none());
+    assert_eq!(
+        erased[2].downcast_ref::<Cow<[u8]>>().unwrap().as_ref(),
+        b"borrowed"
+    );
+    assert!(erased[0].downcast_ref::<Cow<[u8]>>().is_none());
+}
+
+#[test]
+fn test_provenance_aware_registry() {
+    use better_any::registry::ProvenanceAwareRegistry;
+
+    let mut registry = ProvenanceAwareRegistry::new();
+    let s1 = registry.register::<S1>();
+    let s2 = registry.register::<S2<'static>>();
+    assert_ne!(s1, s2);
+
+    let value = S1(5);
+    let direct = &value as &dyn Tid;
+    let bridged: &dyn Tid = (&value).into();
+    assert_eq!(registry.lookup(direct), Some(s1));
+    assert_eq!(registry.lookup(bridged), Some(s1));
+
+    let s = String::from("not static");
+    assert_eq!(registry.lookup(&S2(&s) as &dyn Tid), Some(s2));
+    assert_eq!(registry.lookup(&S4(5usize) as &dyn Tid), None);
+}
+
+#[test]
+fn test_tid_map() {
+    use better_any::map::TidMap;
+
+    let s = String::from("first");
+    let mut map = TidMap::new();
+    assert!(map.insert(S2(&s)).is_none());
+    assert!(map.insert(S1(1)).is_none());
+    assert_eq!(map.insert(S2("second")).unwrap().0, "first");
+    map.get_mut::<S1>().unwrap().0 += 1;
+    assert_eq!(map.get::<S1>().unwrap().0, 2);
+    assert_eq!(map.remove::<S2>().unwrap().0, "second");
+    assert!(map.remove::<S2>().is_none());
+    assert!(map.get::<S4<S1>>().is_none());
+}
+
+#[test]
+fn test_boxed_slice_of_trait_objects() {
+    let s = String::from("element");
+    let buffer: Box<[Box<dyn Tid>]> =
+        vec![Box::new(S1(1)) as Box<dyn Tid>, Box::new(S2(&s))].into();
+    let erased = Box::new(buffer) as Box<dyn Tid>;
+    assert!(erased.downcast_ref::<Vec<Box<dyn Tid>>>().is_none());
+
+    let buffer = erased.downcast_box::<Box<[Box<dyn Tid>]>>().ok().unwrap();
+    assert_eq!((*buffer[0]).downcast_ref::<S1>().unwrap().0, 1);
+    assert_eq!((*buffer[1]).downcast_ref::<S2>().unwrap().0, "element");
+    assert!((*buffer[1]).downcast_ref::<S1>().is_none());
+}
+
+#[test]
+fn test_provider() {
+    use better_any::provider::{Demand, Provider, ProviderExt};
+
+    #[derive(Tid)]
+    struct Context<'a> {
+        name: S2<'a>,
+        id: S1,
+    }
+    impl<'x> Provider<'x> for Context<'x> {
+        fn provide<'a>(&'a self, demand: &mut Demand<'a, 'x>) {
+            demand
+                .provide_ref(&self.name)
+                .provide_ref(&self.id)
+                .provide_value(|| S2(self.name.0));
+        }
+    }
+
+    let s = String::from("context");
+    let context = Context {
+        name: S2(&s),
+        id: S1(7),
+    };
+    let provider = &context as &dyn Provider;
+    assert_eq!(provider.request_ref::<S2>().unwrap().0, "context");
+    assert_eq!(provider.request_ref::<S1>().unwrap().0, 7);
+    assert_eq!(provider.request_value::<S2>().unwrap().0, "context");
+    assert!(provider.request_value::<S1>().is_none());
+}
+
+#[test]
+fn test_tid_enum_dispatch() {
+    use better_any::tid_enum_dispatch;
+
+    enum Value<'a> {
+        Id(S1),
+        Name(S2<'a>),
+    }
+    tid_enum_dispatch!(Value<'a> { S1 => Id, S2<'a> => Name });
+
+    enum Static {
+        Id(S1),
+        Ids(S4<S1>),
+    }
+    tid_enum_dispatch!(Static { S1 => Id, S4<S1> => Ids });
+
+    let s = String::from("name");
+    match Value::from_tid_box(Box::new(S2(&s))) {
+        Ok(Value::Name(name)) => assert_eq!(name.0, "name"),
+        _ => panic!("wrong variant"),
+    }
+    match Value::from_tid_box(Box::new(S1(1))) {
+        Ok(Value::Id(id)) => assert_eq!(id.0, 1),
+        _ => panic!("wrong variant"),
+    }
+    assert!(Value::from_tid_box(Box::new(S4(S1(1)))).is_err());
+
+    match Static::from_tid_box(Box::new(S4(S1(2)))) {
+        Ok(Static::Ids(ids)) => assert_eq!((ids.0).0, 2),
+        _ => panic!("wrong variant"),
+    }
+    match Static::from_tid_box(Box::new(S1(3))) {
+        Ok(Static::Id(id)) => assert_eq!(id.0, 3),
+        _ => panic!("wrong variant"),
+    }
+    let rejected = Static::from_tid_box(Box::new(S2(&s))).err().unwrap();
+    assert!((*rejected).is::<S2>());
+}
+
+#[test]
+fn test_provider_request_functions() {
+    use better_any::provider::{request_ref, request_value, Demand, Provider};
+
+    #[derive(Tid)]
+    struct Names<'a> {
+        first: S2<'a>,
+        second: S3<'a, S1>,
+    }
+    impl<'x> Provider<'x> for Names<'x> {
+        fn provide<'a>(&'a self, demand: &mut Demand<'a, 'x>) {
+            demand
+                .provide_ref(&self.first)
+                .provide_ref(&self.second)
+                .provide_value(|| S3(self.second.0));
+        }
+    }
+
+    let s = String::from("first");
+    let id = S1(2);
+    let names = Names {
+        first: S2(&s),
+        second: S3(&id),
+    };
+    assert_eq!(request_ref::<S2>(&names).unwrap().0, "first");
+    assert_eq!((request_ref::<S3<S1>>(&names).unwrap().0).0, 2);
+    assert!(request_ref::<S1>(&names).is_none());
+    assert_eq!((request_value::<S3<S1>>(&names).unwrap().0).0, 2);
+    assert!(request_value::<S2>(&names).is_none());
+}
+
+#[test]
+fn test_nested_shared_trait_objects() {
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    let s = String::from("inner");
+    let inner = Rc::new(S2(&s)) as Rc<dyn Tid>;
+    let outer = Rc::new(inner.clone()) as Rc<dyn Tid>;
+    assert!(outer.downcast_ref::<Rc<S2>>().is_none());
+    let outer = outer.downcast_rc::<Rc<dyn Tid>>().ok().unwrap();
+    assert_eq!((**outer).downcast_ref::<S2>().unwrap().0, "inner");
+
+    let list = vec![inner.clone(), Rc::new(S1(1))];
+    let list = &list as &dyn Tid;
+    let list = list.downcast_ref::<Vec<Rc<dyn Tid>>>().unwrap();
+    assert_eq!((*list[0]).downcast_ref::<S2>().unwrap().0, "inner");
+    assert_eq!((*list[1]).downcast_ref::<S1>().unwrap().0, 1);
+
+    // only nesting of `Arc<dyn Tid>` is checked here, it is not sent across threads
+    #[allow(clippy::arc_with_non_send_sync)]
+    let arc = Arc::new(Arc::new(S1(5)) as Arc<dyn Tid>) as Arc<dyn Tid>;
+    let arc = arc.downcast_arc::<Arc<dyn Tid>>().ok().unwrap();
+    assert_eq!((**arc).downcast_ref::<S1>().unwrap().0, 5);
+}
+
+#[test]
+fn test_unify_lifetimes() {
+    #[derive(Tid)]
+    #[tid(unify_lifetimes)]
+    struct Pair<'a, 'b, T>(&'a str, &'b T);
+
+    let s = String::from("first");
+    let pair = Pair(&s, &S1(2));
+    let erased = &pair as &dyn Tid;
+    let pair = erased.downcast_ref::<Pair<S1>>().unwrap();
+    assert_eq!(pair.0, "first");
+    assert_eq!((pair.1).0, 2);
+    assert!(erased.downcast_ref::<Pair<S2>>().is_none());
+}
+
+#[test]
+fn test_downcast_ref_or_and_cow() {
+    use std::borrow::Cow;
+
+    #[derive(Tid, Clone, PartialEq, Debug)]
+    struct Label<'a>(&'a str);
+
+    let s = String::from("label");
+    let label = Label(&s);
+    let other = S1(1);
+    let fallback = Label("fallback");
+
+    let matching = &label as &dyn Tid;
+    let mismatching = &other as &dyn Tid;
+    assert_eq!(matching.downcast_ref_or(&fallback).0, "label");
+    assert_eq!(mismatching.downcast_ref_or(&fallback).0, "fallback");
+
+    assert!(matches!(
+        matching.downcast_cow(|| fallback.clone()),
+        Cow::Borrowed(Label("label"))
+    ));
+    assert_eq!(
+        mismatching.downcast_cow(|| fallback.clone()),
+        Cow::<Label>::Owned(Label("fallback"))
+    );
+}
+
+#[test]
+fn test_tid_generic_enum() {
+    enum Either<'a, L, R> {
+        Left(&'a L),
+        Right(R),
+    }
+    tid!(Either<'a, L, R>);
+
+    let s = String::from("left");
+    let left = Either::<S2, S1>::Left(&S2(&s));
+    let erased = &left as &dyn Tid;
+    assert!(erased.downcast_ref::<Either<S1, S2>>().is_none());
+    match erased.downcast_ref::<Either<S2, S1>>().unwrap() {
+        Either::Left(left) => assert_eq!(left.0, "left"),
+        Either::Right(_) => panic!("wrong variant"),
+    }
+
+    let right = Either::<S2, S1>::Right(S1(7));
+    let erased = &right as &dyn Tid;
+    assert!(erased.downcast_ref::<Either<S2, S2>>().is_none());
+    match erased.downcast_ref::<Either<S2, S1>>().unwrap() {
+        Either::Left(_) => panic!("wrong variant"),
+        Either::Right(right) => assert_eq!(right.0, 7),
+    }
+}
+
+#[test]
+fn test_boxed_error() {
+    use std::error::Error;
+    type SendResult = Result<S1, Box<dyn Error + Send + Sync>>;
+    type LocalResult = Result<S1, Box<dyn Error>>;
+
+    let result: SendResult = Err("failed".into());
+    let erased = &result as &dyn Tid;
+    assert!(!erased.is::<LocalResult>());
+    let error = erased.downcast_ref::<SendResult>().unwrap();
+    assert_eq!(error.as_ref().err().unwrap().to_string(), "failed");
+
+    let result: SendResult = Ok(S1(5));
+    let erased = &result as &dyn Tid;
+    assert_eq!(
+        erased
+            .downcast_ref::<SendResult>()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .0,
+        5
+    );
+}
+
+#[test]
+fn test_type_name() {
+    use std::any::type_name;
+    let s = String::from("test");
+    let value = S4(S2(&s));
+    let erased = &value as &dyn Tid;
+    assert_eq!(erased.type_name(), type_name::<S4<S2<'_>>>());
+    assert!(!erased.type_name().contains("__TypeIdGenerator"));
+
+    let boxed: Box<dyn Tid> = Box::new(S1(5));
+    assert_eq!((*boxed).type_name(), type_name::<S1>());
+
+    let bridged: &dyn Tid = (&5u32 as &u32).into();
+    assert_eq!(bridged.type_name(), "u32");
+}
+
+#[test]
+fn test_container_registry() {
+    use better_any::registry::ContainerRegistry;
+    use std::cell::RefCell;
+
+    let s = String::from("inner");
+    let mut registry = ContainerRegistry::new();
+    registry.register::<RefCell<S1>>(|cell, f| f(&*cell.borrow()));
+    registry.register::<RefCell<S2>>(|cell, f| f(&*cell.borrow()));
+
+    let containers: Vec<Box<dyn Tid>> = vec![
+        Box::new(RefCell::new(S1(5))),
+        Box::new(RefCell::new(S2(&s))),
+        Box::new(S1(6)),
+    ];
+    let extracted = containers
+        .iter()
+        .map(|it| {
+            registry.downcast_container(&**it, |inner| match inner.downcast_ref::<S1>() {
+                Some(s1) => s1.0.to_string(),
+                None => inner.downcast_ref::<S2>().unwrap().0.to_string(),
+            })
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        extracted,
+        vec![Some("5".to_string()), Some("inner".to_string()), None]
+    );
+}
+
+#[test]
+fn test_primitive_ids_are_distinct() {
+    use better_any::{any_bridge_id, typeid_of};
+    use std::any::TypeId;
+    use std::collections::HashSet;
+    use std::time::Duration;
+
+    fn ids<T: TidAble<'static>>() -> [TypeId; 2] {
+        [typeid_of::<T>(), any_bridge_id::<T>()]
+    }
+    let all = vec![
+        ids::<u8>(),
+        ids::<u16>(),
+        ids::<u32>(),
+        ids::<u64>(),
+        ids::<u128>(),
+        ids::<usize>(),
+        ids::<i8>(),
+        ids::<i16>(),
+        ids::<i32>(),
+        ids::<i64>(),
+        ids::<i128>(),
+        ids::<isize>(),
+        ids::<f32>(),
+        ids::<f64>(),
+        ids::<bool>(),
+        ids::<char>(),
+        ids::<Duration>(),
+    ];
+    let unique = all.iter().flatten().collect::<HashSet<_>>();
+    assert_eq!(unique.len(), all.len() * 2);
+}
+
+#[test]
+fn test_try_borrow_downcast() {
+    use better_any::cell::{BorrowOrTypeError, RefCellExt};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let s = String::from("value");
+    let cell: Rc<RefCell<dyn Tid>> = Rc::new(RefCell::new(S2(&s)));
+    assert_eq!(cell.try_borrow_downcast::<S2>().unwrap().0, "value");
+    assert!(matches!(
+        cell.try_borrow_downcast::<S1>(),
+        Err(BorrowOrTypeError::TypeMismatch)
+    ));
+
+    let _guard = cell.borrow_mut();
+    assert!(matches!(
+        cell.try_borrow_downcast::<S2>(),
+        Err(BorrowOrTypeError::Borrow(_))
+    ));
+}
+
+#[test]
+fn test_send_sync_downcast() {
+    use std::thread;
+
+    let direct: Box<dyn Tid<'static> + Send> = Box::new(S1(5));
+    let bridged: Box<dyn Tid<'static> + Send + Sync> = Box::new(5usize).into();
+    let (direct, bridged) = thread::spawn(move || {
+        (
+            direct.downcast_box::<S1>().ok().unwrap(),
+            bridged.downcast_any_box::<usize>().ok().unwrap(),
+        )
+    })
+    .join()
+    .unwrap();
+    assert_eq!(direct.0, 5);
+    assert_eq!(*bridged, 5);
+
+    let mut shared: Box<dyn Tid<'static> + Sync> = Box::new(S1(6)).into();
+    assert!(shared.downcast_ref::<S1>().is_none());
+    shared.downcast_any_mut::<S1>().unwrap().0 += 1;
+    let shared = &*shared;
+    thread::scope(|scope| {
+        scope.spawn(|| assert_eq!(shared.downcast_any_ref::<S1>().unwrap().0, 7));
+    });
+}
+
+#[test]
+fn test_derive_const_generic() {
+    #[derive(Tid)]
+    struct Frame<'a, const N: usize>(&'a [u8; N]);
+
+    #[derive(Tid)]
+    struct Padded<T, const N: usize = 4>([T; N]);
+
+    let (small, large) = ([1u8; 8], [2u8; 16]);
+    let small = &Frame(&small) as &dyn Tid;
+    let large = &Frame(&large) as &dyn Tid;
+    assert_ne!(small.self_id(), large.self_id());
+    assert_eq!(small.downcast_ref::<Frame<8>>().unwrap().0, &[1u8; 8]);
+    assert_eq!(large.downcast_ref::<Frame<16>>().unwrap().0, &[2u8; 16]);
+    assert!(small.downcast_ref::<Frame<16>>().is_none());
+
+    let padded = &Padded([S1(1), S1(2), S1(3), S1(4)]) as &dyn Tid;
+    assert!(padded.is::<Padded<S1>>());
+    assert!(!padded.is::<Padded<S1, 3>>());
+}
+
+#[test]
+fn test_array() {
+    let s = String::from("array");
+    let pair = [S1(1), S1(2)];
+    let pair = &pair as &dyn Tid;
+    assert!(pair.downcast_ref::<[S1; 3]>().is_none());
+    assert!(pair.downcast_ref::<[S4<S1>; 2]>().is_none());
+    assert_eq!(pair.downcast_ref::<[S1; 2]>().unwrap()[1].0, 2);
+
+    let names = [S2(&s), S2(&s), S2(&s), S2(&s)];
+    let names = &names as &dyn Tid;
+    assert_eq!(names.downcast_ref::<[S2; 4]>().unwrap()[3].0, "array");
+}
+
+#[test]
+fn test_provider_chain() {
+    use better_any::provider::{request_ref, Demand, Provider, ProviderExt};
+
+    #[derive(Tid)]
+    struct Session<'a> {
+        user: S2<'a>,
+    }
+    impl<'x> Provider<'x> for Session<'x> {
+        fn provide<'a>(&'a self, demand: &mut Demand<'a, 'x>) {
+            demand.provide_ref(&self.user).provide_value(|| S1(1));
+        }
+    }
+
+    #[derive(Tid)]
+    struct Request<'a> {
+        id: S1,
+        session: Session<'a>,
+    }
+    impl<'x> Provider<'x> for Request<'x> {
+        fn provide<'a>(&'a self, demand: &mut Demand<'a, 'x>) {
+            demand.provide_ref(&self.id).provide_tid_ref(&self.session);
+        }
+    }
+
+    let s = String::from("user");
+    let request = Request {
+        id: S1(5),
+        session: Session { user: S2(&s) },
+    };
+    let user: &S2<'_> = request.request_ref().unwrap();
+    assert_eq!(user.0, "user");
+    assert_eq!(request_ref::<S1>(&request).unwrap().0, 5);
+    assert!(request.request_value::<S1>().is_none());
+}
+
+#[test]
+fn test_tuples() {
+    let s = String::from("tuple");
+    let triple = (S1(1), S2(&s), S4(S1(3)));
+    let erased = &triple as &dyn Tid;
+    assert!(!erased.is::<(S1, S2)>());
+    assert!(!erased.is::<(S1, S2, S4<S1>, S1)>());
+    let (a, b, c) = erased.downcast_ref::<(S1, S2, S4<S1>)>().unwrap();
+    assert_eq!((a.0, b.0, (c.0).0), (1, "tuple", 3));
+
+    let pair = &(S1(1), S2(&s)) as &dyn Tid;
+    assert!(!pair.is::<(S1, S2, S4<S1>)>());
+    assert!(!pair.is::<(S2, S1)>());
+    assert!((&(S1(1),) as &dyn Tid).is::<(S1,)>());
+}
+
+#[test]
+fn test_buf_reader() {
+    use std::io::{BufRead, BufReader, BufWriter, Cursor};
+
+    let data = b"first\nsecond\n".to_vec();
+    let mut reader: Box<dyn Tid> = Box::new(BufReader::new(Cursor::new(data)));
+    assert!(!(*reader).is::<BufReader<Cursor<String>>>());
+    assert!(!(*reader).is::<BufWriter<Cursor<Vec<u8>>>>());
+
+    let mut line = String::new();
+    let typed = (*reader)
+        .downcast_mut::<BufReader<Cursor<Vec<u8>>>>()
+        .unwrap();
+    typed.read_line(&mut line).unwrap();
+    assert_eq!(line, "first\n");
+
+    let reader = reader
+        .downcast_box::<BufReader<Cursor<Vec<u8>>>>()
+        .ok()
+        .unwrap();
+    assert_eq!(reader.lines().next().unwrap().unwrap(), "second");
+}
+
+#[test]
+fn test_downcast_error() {
+    use better_any::typeid_of;
+    use std::any::type_name;
+
+    fn take_s1<'a>(value: Box<dyn Tid<'a> + 'a>) -> Result<usize, Box<dyn std::error::Error + 'a>> {
+        Ok(value.downcast::<S1>()?.0)
+    }
+
+    let s = String::from("error");
+    assert_eq!(take_s1(Box::new(S1(5))).unwrap(), 5);
+    let err = take_s1(Box::new(S2(&s))).err().unwrap();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "expected `{}`, found `{}`",
+            type_name::<S1>(),
+            type_name::<S2>()
+        )
+    );
+
+    let err = (Box::new(S2(&s)) as Box<dyn Tid>)
+        .downcast::<S1>()
+        .err()
+        .unwrap();
+    assert_eq!(err.expected, typeid_of::<S1>());
+    assert_eq!(err.actual, typeid_of::<S2>());
+    assert_eq!(err.value.downcast::<S2>().unwrap().0, "error");
+}
+
+#[cfg(feature = "parking_lot")]
+#[test]
+fn test_parking_lot_guards() {
+    use better_any::parking_lot::TidGuard;
+    use parking_lot::{Mutex, RwLock};
+
+    let s = String::from("lock");
+    let mutex: Box<Mutex<dyn Tid>> = Box::new(Mutex::new(S1(1)));
+    let guard = mutex.lock().downcast_guard::<S2>().err().unwrap();
+    guard.downcast_guard::<S1>().ok().unwrap().0 += 1;
+    assert_eq!(mutex.lock().downcast_guard::<S1>().ok().unwrap().0, 2);
+
+    let lock: Box<RwLock<dyn Tid>> = Box::new(RwLock::new(S2(&s)));
+    {
+        let first = lock.read().downcast_guard::<S2>().ok().unwrap();
+        let second = lock.read().downcast_guard::<S2>().ok().unwrap();
+        assert_eq!((first.0, second.0), ("lock", "lock"));
+        assert!(lock.try_write().is_none());
+    }
+    let mut guard = lock.write().downcast_guard::<S2>().ok().unwrap();
+    guard.0 = "changed";
+    drop(guard);
+    assert!(lock.read().downcast_guard::<S1>().is_err());
+    assert_eq!(
+        lock.read().downcast_guard::<S2>().ok().unwrap().0,
+        "changed"
+    );
+}
+
+#[test]
+fn test_downcast_borrow() {
+    use better_any::borrow::{BorrowKind, Exclusive, Shared};
+
+    fn first<'b, 'a: 'b, B: BorrowKind>(
+        values: Vec<B::Ref<'b, dyn Tid<'a> + 'a>>,
+    ) -> Option<B::Ref<'b, S2<'a>>> {
+        values
+            .into_iter()
+            .find_map(|it| <dyn Tid>::downcast_borrow::<B, S2>(it))
+    }
+
+    let s = String::from("borrow");
+    let mut values: Vec<Box<dyn Tid>> = vec![Box::new(S1(1)), Box::new(S2(&s))];
+    let found = first::<Exclusive>(values.iter_mut().map(|it| &mut **it).collect()).unwrap();
+    found.0 = "changed";
+    let found = first::<Shared>(values.iter().map(|it| &**it).collect()).unwrap();
+    assert_eq!(found.0, "changed");
+    assert!(<dyn Tid>::downcast_borrow::<Shared, S1>(&S2(&s)).is_none());
+}
+
+#[test]
+fn test_btree_set() {
+    use std::collections::BTreeSet;
+
+    let set = vec![S1(3), S1(1), S1(2)]
+        .into_iter()
+        .collect::<BTreeSet<_>>();
+    let erased = &set as &dyn Tid;
+    assert!(!erased.is::<BTreeSet<S4<S1>>>());
+    assert!(!erased.is::<std::collections::HashSet<S1>>());
+    let set = erased.downcast_ref::<BTreeSet<S1>>().unwrap();
+    assert_eq!(set.iter().map(|it| it.0).collect::<Vec<_>>(), vec![1, 2, 3]);
+}
+
+#[test]
+fn test_any_rc_arc() {
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    let rc = Rc::new(S1(5));
+    let erased = <dyn Tid>::from_any_rc(rc.clone());
+    assert!((*erased).downcast_ref::<S1>().is_none());
+    let erased = erased.downcast_any_rc::<usize>().err().unwrap();
+    let typed = erased.downcast_any_rc::<S1>().ok().unwrap();
+    assert!(Rc::ptr_eq(&rc, &typed));
+
+    let arc = <dyn Tid>::from_any_arc(Arc::new(String::from("arc")));
+    assert_eq!(arc.clone().downcast_any_ref::<String>().unwrap(), "arc");
+    assert_eq!(*arc.downcast_any_arc::<String>().ok().unwrap(), "arc");
+}
+
+#[test]
+fn test_downcast_via() {
+    use better_any::downcast_via;
+
+    trait Named<'a>: Tid<'a> {}
+    impl<'a> Named<'a> for S2<'a> {}
+    impl<'a> Named<'a> for S1 {}
+
+    fn names<'b, 'a, Tr: ?Sized + Tid<'a>>(objects: &'b [&'b Tr]) -> Vec<&'a str> {
+        objects
+            .iter()
+            .filter_map(|it| downcast_via::<Tr, S2>(*it))
+            .map(|it| it.0)
+            .collect()
+    }
+
+    let s = String::from("named");
+    let (first, second) = (S2(&s), S1(1));
+    let objects = [&first as &dyn Named, &second as &dyn Named];
+    assert_eq!(names(&objects), vec!["named"]);
+}
+
+#[test]
+fn test_maps() {
+    use std::collections::{BTreeMap, HashMap};
+
+    let (first, second) = (String::from("first"), String::from("second"));
+    let map = vec![(2u32, S2(&second)), (1, S2(&first))]
+        .into_iter()
+        .collect::<BTreeMap<_, _>>();
+    let erased = &map as &dyn Tid;
+    assert!(!erased.is::<BTreeMap<u64, S2>>());
+    assert!(!erased.is::<HashMap<u32, S2>>());
+    let map = erased.downcast_ref::<BTreeMap<u32, S2>>().unwrap();
+    assert_eq!(
+        map.values().map(|it| it.0).collect::<Vec<_>>(),
+        vec!["first", "second"]
+    );
+
+    let map = vec![(String::from("key"), S1(1))]
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+    let erased = &map as &dyn Tid;
+    assert!(!erased.is::<HashMap<
+        String,
+        S1,
+        std::hash::BuildHasherDefault<std::collections::hash_map::DefaultHasher>,
+    >>());
+    assert_eq!(
+        erased.downcast_ref::<HashMap<String, S1>>().unwrap()["key"].0,
+        1
+    );
+}
+
+#[test]
+fn test_smart_pointers_as_ref() {
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    // `AsRef<T>` for `Box<T>`, `Rc<T>` and `Arc<T>` is provided by std
+    fn value<'a>(it: impl AsRef<dyn Tid<'a> + 'a>) -> Option<usize> {
+        it.as_ref().downcast_ref::<S1>().map(|it| it.0)
+    }
+
+    assert_eq!(value(Box::new(S1(1)) as Box<dyn Tid>), Some(1));
+    assert_eq!(value(Rc::new(S1(2)) as Rc<dyn Tid>), Some(2));
+    assert_eq!(value(Arc::new(S1(3)) as Arc<dyn Tid>), Some(3));
+    assert_eq!(value(Box::new(S4(S1(4))) as Box<dyn Tid>), None);
+}
+
+#[test]
+fn test_sequences() {
+    use better_any::typeid_of;
+    use std::collections::{BinaryHeap, LinkedList, VecDeque};
+
+    let ids = [
+        typeid_of::<Vec<S1>>(),
+        typeid_of::<VecDeque<S1>>(),
+        typeid_of::<LinkedList<S1>>(),
+        typeid_of::<BinaryHeap<S1>>(),
+    ];
+    for (i, first) in ids.iter().enumerate() {
+        for second in &ids[i + 1..] {
+            assert_ne!(first, second);
+        }
+    }
+
+    let deque = vec![S1(1), S1(2)].into_iter().collect::<VecDeque<_>>();
+    let erased = &deque as &dyn Tid;
+    assert!(!erased.is::<Vec<S1>>());
+    assert_eq!(erased.downcast_ref::<VecDeque<S1>>().unwrap()[1].0, 2);
+
+    let heap = vec![S1(1), S1(3), S1(2)]
+        .into_iter()
+        .collect::<BinaryHeap<_>>();
+    let erased = &heap as &dyn Tid;
+    assert_eq!(
+        erased
+            .downcast_ref::<BinaryHeap<S1>>()
+            .unwrap()
+            .peek()
+            .unwrap()
+            .0,
+        3
+    );
+}
+
+#[test]
+fn test_downcast_ref_logged() {
+    let value = &S1(5) as &dyn Tid;
+    let (typed, id) = value.downcast_ref_logged::<S1>().unwrap();
+    assert_eq!(typed.0, 5);
+    assert_eq!(id, S1::id());
+    assert_eq!(id, value.self_id());
+    assert!(value.downcast_ref_logged::<S4<S1>>().is_none());
+}
+
+#[test]
+fn test_phantom_data() {
+    use std::marker::PhantomData;
+
+    #[derive(Tid)]
+    struct Marked<'a, T> {
+        marker: PhantomData<S2<'a>>,
+        value: T,
+    }
+
+    let marked = Marked {
+        marker: PhantomData,
+        value: PhantomData::<S2>,
+    };
+    let erased = &marked as &dyn Tid;
+    assert!(!erased.is::<Marked<PhantomData<S1>>>());
+    let marked = erased.downcast_ref::<Marked<PhantomData<S2>>>().unwrap();
+    assert_eq!(marked.value, PhantomData);
+    assert!((&PhantomData::<str> as &dyn Tid).is::<PhantomData<str>>());
+}
+
+#[test]
+fn test_tid_to_owned() {
+    use better_any::TidToOwned;
+    use std::borrow::Borrow;
+
+    fn find_borrowed<'b, 'a, T: ?Sized + TidToOwned<'a>>(
+        values: &'b [Box<dyn Tid<'a> + 'a>],
+    ) -> Option<&'b T> {
+        values
+            .iter()
+            .find_map(|it| (**it).downcast_ref::<T::Owned>())
+            .map(|it| it.borrow())
+    }
+
+    let values: Vec<Box<dyn Tid>> = vec![
+        Box::new(S1(1)),
+        Box::new(vec![1u8, 2]),
+        Box::new(String::from("owned")),
+    ];
+    assert_eq!(find_borrowed::<str>(&values), Some("owned"));
+    assert_eq!(find_borrowed::<[u8]>(&values), Some(&[1u8, 2][..]));
+    assert_eq!(find_borrowed::<[u16]>(&values), None);
+}
+
+#[test]
+fn test_borrowed_slices() {
+    let (first, second) = (String::from("first"), String::from("second"));
+    let names = [S2(&first), S2(&second)];
+    let slice = &names[..];
+    let erased = &slice as &dyn Tid;
+    assert!(!erased.is::<&[S1]>());
+    assert!(!erased.is::<&Vec<S1>>());
+    assert!(!erased.is::<Vec<S2>>());
+    let slice = erased.downcast_ref::<&[S2]>().unwrap();
+    assert_eq!(slice[1].0, "second");
+
+    let text: &str = &first;
+    let erased = &text as &dyn Tid;
+    assert!(!erased.is::<&String>());
+    assert_eq!(*erased.downcast_ref::<&str>().unwrap(), "first");
+}
+
+#[test]
+fn test_dispatch_cache() {
+    use better_any::dispatch::DispatchCache;
+    use std::cell::Cell;
+
+    let s = String::from("second");
+    let objects: Vec<Box<dyn Tid>> = vec![Box::new(S1(1)), Box::new(S2(&s)), Box::new(S1(3))];
+    fn classify<'a>(builds: &Cell<usize>, it: &(dyn Tid<'a> + 'a)) -> &'static str {
+        builds.set(builds.get() + 1);
+        if it.is::<S1>() {
+            "s1"
+        } else {
+            "other"
+        }
+    }
+
+    let builds = Cell::new(0);
+    let cache = DispatchCache::new();
+    for _ in 0..3 {
+        let resolved = objects
+            .iter()
+            .map(|it| cache.resolve(&**it, |it| classify(&builds, it)))
+            .collect::<Vec<_>>();
+        assert_eq!(resolved, vec!["s1", "other", "s1"]);
+    }
+    assert_eq!(builds.get(), 3);
+    assert_eq!(cache.len(), 3);
+
+    cache.clear();
+    cache.resolve(&*objects[0], |it| classify(&builds, it));
+    assert_eq!(builds.get(), 4);
+}
+
+#[test]
+fn test_tid_dispatcher() {
+    use better_any::dispatch::TidDispatcher;
+
+    let s = String::from("second");
+    let prefix = String::from("s2:");
+    let mut dispatcher = TidDispatcher::new(|it| format!("default {}", it.type_name()));
+    dispatcher.register(|it: &S1| format!("s1:{}", it.0));
+    dispatcher.register(|it: &S2| format!("{}{}", prefix, it.0));
+
+    let objects: Vec<Box<dyn Tid>> = vec![Box::new(S1(1)), Box::new(S2(&s)), Box::new(S4(3u8))];
+    let results = objects
+        .iter()
+        .map(|it| dispatcher.dispatch(&**it))
+        .collect::<Vec<_>>();
+    assert_eq!(results[..2], ["s1:1", "s2:second"]);
+    assert!(results[2].starts_with("default"), "{}", results[2]);
+    assert!(results[2].contains("S4"), "{}", results[2]);
+    assert!(dispatcher.is_registered(&*objects[1]));
+    assert!(!dispatcher.is_registered(&*objects[2]));
+}
+
+#[test]
+fn test_array_into_iter() {
+    use std::array::IntoIter;
+
+    let iter: Box<dyn Tid> = Box::new(IntoIterator::into_iter([S1(1), S1(2), S1(3)]));
+    assert!(!(*iter).is::<IntoIter<S1, 2>>());
+    assert!(!(*iter).is::<[S1; 3]>());
+    let iter = iter.downcast_box::<IntoIter<S1, 3>>().ok().unwrap();
+    assert_eq!(iter.map(|it| it.0).collect::<Vec<_>>(), vec![1, 2, 3]);
+}
+
+#[test]
+fn test_try_downcast_move() {
+    use std::rc::Rc;
+
+    fn unwrap_rc<'a, T: TidExt<'a>>(value: T) -> Result<usize, T> {
+        let value = match value.try_downcast_move::<S1>() {
+            Ok(it) => return Ok(it.0),
+            Err(value) => value,
+        };
+        value.try_downcast_move::<Rc<S1>>().map(|it| it.0)
+    }
+
+    let rc = Rc::new(S1(5));
+    assert_eq!(unwrap_rc(rc.clone()).ok(), Some(5));
+    assert_eq!(Rc::strong_count(&rc), 1);
+    assert_eq!(unwrap_rc(S1(6)).ok(), Some(6));
+
+    let s = String::from("moved");
+    let value = unwrap_rc(S2(&s)).err().unwrap();
+    assert_eq!(value.0, "moved");
+    let other = Rc::new(S2(&s));
+    let returned = unwrap_rc(other.clone()).err().unwrap();
+    assert!(Rc::ptr_eq(&returned, &other));
+    drop(returned);
+    assert_eq!(Rc::strong_count(&other), 1);
+}
+
+#[test]
+fn test_into_dyn() {
+    let s = String::from("into");
+    let boxed = S2(&s).into_dyn_box();
+    assert_eq!((*boxed).downcast_ref::<S2>().unwrap().0, "into");
+    let rc = S1(1).into_dyn_rc();
+    assert_eq!(rc.downcast_rc::<S1>().ok().unwrap().0, 1);
+    let arc = S1(2).into_dyn_arc();
+    assert_eq!(arc.downcast_arc::<S1>().ok().unwrap().0, 2);
+}
+
+#[test]
+fn test_reference_tuples() {
+    let (id, name) = (S1(1), String::from("name"));
+    let pair = (&id, &name);
+    let erased = &pair as &dyn Tid;
+    assert!(!erased.is::<(&S1, &String, &S1)>());
+    assert!(!erased.is::<(&String, &S1)>());
+    let (id, name) = erased.downcast_ref::<(&S1, &String)>().unwrap();
+    assert_eq!((id.0, name.as_str()), (1, "name"));
+
+    let triple = (&1u8, &2u16, &3u32);
+    let erased = &triple as &dyn Tid;
+    assert_eq!(*erased.downcast_ref::<(&u8, &u16, &u32)>().unwrap().2, 3);
+}
+
+#[test]
+fn test_erased_layout() {
+    use std::alloc::Layout;
+
+    let s = String::from("layout");
+    let values: Vec<Box<dyn Tid>> = vec![Box::new(S1(1)), Box::new(S2(&s)), Box::new([0u8; 3])];
+    assert_eq!(values[0].erased_layout(), Layout::new::<S1>());
+    assert_eq!(values[1].erased_layout(), Layout::new::<S2>());
+    assert_eq!(values[2].erased_layout(), Layout::new::<[u8; 3]>());
+}
+
+#[test]
+fn test_derive_marker_does_not_leak() {
+    mod inner {
+        use better_any::{Tid, TidAble};
+
+        // user items with the same name as derive internals are not affected
+        #[allow(dead_code)]
+        pub struct __TypeIdGenerator;
+
+        #[derive(Tid)]
+        pub struct First<T>(pub T);
+        #[derive(Tid)]
+        pub struct Second<'a, T>(pub &'a str, pub T);
+    }
+    use inner::*;
+
+    let s = String::from("second");
+    let first = &First(S1(1)) as &dyn Tid;
+    let second = &Second(&s, S1(2)) as &dyn Tid;
+    assert!(!first.is::<Second<S1>>());
+    assert!(!second.is::<First<S1>>());
+    assert_eq!(second.downcast_ref::<Second<S1>>().unwrap().0, "second");
+}
+
+#[cfg(feature = "saturating")]
+#[test]
+fn test_saturating_reference() {
+    use std::num::Saturating;
+
+    let value = 5i32;
+    let wrapped = Saturating(&value);
+    let erased = &wrapped as &dyn Tid;
+    assert!(!erased.is::<Saturating<i32>>());
+    assert!(!erased.is::<&i32>());
+    assert!(!erased.is::<Saturating<&mut i32>>());
+    assert_eq!(*erased.downcast_ref::<Saturating<&i32>>().unwrap().0, 5);
+
+    let nested = Saturating(Saturating(&value));
+    let erased = &nested as &dyn Tid;
+    assert!(!erased.is::<Saturating<&i32>>());
+    assert_eq!(
+        *(erased
+            .downcast_ref::<Saturating<Saturating<&i32>>>()
+            .unwrap()
+            .0)
+            .0,
+        5
+    );
+}
+
+#[test]
+fn test_pin() {
+    let s = String::from("pinned");
+    let values: Vec<Box<dyn Tid>> = vec![Box::new(Box::pin(S2(&s))), Box::new(Box::new(S2(&s)))];
+    assert!(!(*values[0]).is::<Box<S2>>());
+    assert!(!(*values[1]).is::<std::pin::Pin<Box<S2>>>());
+    let mut values = values.into_iter();
+    let pinned = values
+        .next()
+        .unwrap()
+        .downcast_box::<std::pin::Pin<Box<S2>>>()
+        .ok()
+        .unwrap();
+    assert_eq!(pinned.0, "pinned");
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn test_tracing_field() {
+    use better_any::field::TidField;
+    use std::fmt::Debug;
+    use std::sync::Mutex;
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<String>>);
+    impl Visit for &Recorder {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("{}={:?}", field.name(), value));
+        }
+    }
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, _: &Attributes<'_>) -> Id {
+            Id::from_u64(1)
+        }
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+        fn event(&self, event: &Event<'_>) {
+            event.record(&mut &*self);
+        }
+        fn enter(&self, _: &Id) {}
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[derive(Debug, Tid)]
+    struct Named<'a>(&'a str);
+
+    let s = String::from("named");
+    let named = Named(&s);
+    let erased = &S1(1) as &dyn Tid;
+    let recorder = std::sync::Arc::new(Recorder::default());
+    tracing::subscriber::with_default(recorder.clone(), || {
+        tracing::info!(opaque = TidField::new(erased).value(), debug = ?TidField::with_debug(&named));
+    });
+    let records = recorder.0.lock().unwrap();
+    assert!(records
+        .iter()
+        .any(|it| it.starts_with("opaque=") && it.ends_with("S1")));
+    let expected = format!(": Named({:?})", named.0);
+    assert!(records
+        .iter()
+        .any(|it| it.starts_with("debug=") && it.ends_with(&expected)));
+}
+
+#[test]
+fn test_downcast_smart() {
+    use better_any::cast::downcast_smart;
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    let s = String::from("test");
+    let boxed = Box::new(S2(&s)) as Box<dyn Tid>;
+    let boxed: Box<S2> = downcast_smart(boxed).unwrap_or_else(|_| panic!("error"));
+    assert_eq!(boxed.0, "test");
+
+    let rc = Rc::new(S1(5)) as Rc<dyn Tid>;
+    let rc = downcast_smart::<_, Rc<S2>>(rc).err().unwrap();
+    let rc: Rc<S1> = downcast_smart(rc).unwrap_or_else(|_| panic!("error"));
+    assert_eq!(rc.0, 5);
+
+    let arc = Arc::new(S2(&s)) as Arc<dyn Tid + Send + Sync>;
+    let arc: Arc<S2> = downcast_smart(arc).unwrap_or_else(|_| panic!("error"));
+    assert_eq!(arc.0, "test");
+}
+
+#[test]
+fn test_downcast_ref_mut_guard() {
+    use better_any::cell::downcast_ref_mut_guard;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let cell: Rc<RefCell<dyn Tid>> = Rc::new(RefCell::new(S1(1)));
+    let guard = match downcast_ref_mut_guard::<S2, _>(cell.borrow_mut()) {
+        Ok(_) => panic!("wrong type"),
+        Err(guard) => guard,
+    };
+    let mut value = downcast_ref_mut_guard::<S1, _>(guard).ok().unwrap();
+    value.0 = 10;
+    assert!(cell.try_borrow().is_err());
+    drop(value);
+    assert_eq!(cell.borrow().downcast_ref::<S1>().unwrap().0, 10);
+}
+
+#[test]
+fn test_into_tid_box() {
+    use better_any::into_tid_box;
+
+    let any: Box<dyn Any> = Box::new(S1(5));
+    let mut tid = into_tid_box(any);
+    assert!((*tid).downcast_ref::<S1>().is_none());
+    tid.downcast_any_mut::<S1>().unwrap().0 = 6;
+    assert_eq!(tid.downcast_any_ref::<S1>().unwrap().0, 6);
+    assert!(tid.downcast_any_ref::<usize>().is_none());
+    let tid = tid.downcast_any_box::<usize>().err().unwrap();
+    assert_eq!(tid.downcast_any_box::<S1>().ok().unwrap().0, 6);
+}
+
+#[test]
+fn test_swap_erased() {
+    use better_any::swap_erased;
+
+    let s = String::from("test");
+    let mut values: Vec<Box<dyn Tid>> = vec![Box::new(S1(1)), Box::new(S2(&s)), Box::new(S1(2))];
+    let (first, rest) = values.split_at_mut(1);
+    swap_erased(&mut *first[0], &mut *rest[1]).unwrap();
+    let err = swap_erased(&mut *first[0], &mut *rest[0]).unwrap_err();
+    assert_eq!(err.expected, S1::id());
+    assert_eq!(err.actual, S2::id());
+    assert_eq!((*values[0]).downcast_ref::<S1>().unwrap().0, 2);
+    assert_eq!((*values[2]).downcast_ref::<S1>().unwrap().0, 1);
+}
+
+#[test]
+fn test_io_combinators() {
+    use std::io::{Chain, Cursor, Read, Take};
+
+    let chain = Cursor::new(b"first ".to_vec()).chain(Cursor::new(b"second".to_vec()));
+    let reader: Box<dyn Tid> = Box::new(chain);
+    assert!(!(*reader).is::<Take<Cursor<Vec<u8>>>>());
+    let mut chain = reader
+        .downcast_box::<Chain<Cursor<Vec<u8>>, Cursor<Vec<u8>>>>()
+        .ok()
+        .unwrap();
+    let mut text = String::new();
+    chain.read_to_string(&mut text).unwrap();
+    assert_eq!(text, "first second");
+
+    let s = String::from("taken and more");
+    let mut reader: Box<dyn Tid> = Box::new(Cursor::new(s.as_str()).take(5));
+    let mut text = String::new();
+    (*reader)
+        .downcast_mut::<Take<Cursor<&str>>>()
+        .unwrap()
+        .read_to_string(&mut text)
+        .unwrap();
+    assert_eq!(text, "taken");
+}
+
+#[test]
+fn test_downcast_in_option() {
+    use better_any::downcast_in_option;
+
+    let s = String::from("test");
+    let mut opt: Option<Box<dyn Tid>> = Some(Box::new(S2(&s)));
+    assert!(downcast_in_option::<S1>(&mut opt).is_none());
+    assert!(opt.as_deref().unwrap().is::<S2>());
+    assert_eq!(downcast_in_option::<S2>(&mut opt).unwrap().0, "test");
+    assert!(opt.is_none());
+    assert!(downcast_in_option::<S2>(&mut opt).is_none());
+}
+
+#[test]
+fn test_tid_list() {
+    struct A;
+    struct B<'a>(&'a str);
+    struct C(usize);
+    tid!(A, B<'a>, C,);
+
+    let s = String::from("test");
+    let values: [Box<dyn Tid>; 3] = [Box::new(A), Box::new(B(&s)), Box::new(C(5))];
+    assert!((*values[0]).is::<A>());
+    assert_eq!((*values[1]).downcast_ref::<B>().unwrap().0, "test");
+    assert_eq!((*values[2]).downcast_ref::<C>().unwrap().0, 5);
+    assert!(!(*values[2]).is::<A>());
+}
+
+#[test]
+fn test_join_handle() {
+    use std::thread::JoinHandle;
+
+    let handles: Vec<Box<dyn Tid>> = vec![
+        Box::new(std::thread::spawn(|| 5u32)),
+        Box::new(std::thread::spawn(|| String::from("done"))),
+    ];
+    let mut results = Vec::new();
+    for handle in handles {
+        let handle = match handle.downcast_box::<JoinHandle<u32>>() {
+            Ok(handle) => {
+                results.push(handle.join().unwrap().to_string());
+                continue;
+            }
+            Err(handle) => handle,
+        };
+        let handle = handle.downcast_box::<JoinHandle<String>>().ok().unwrap();
+        results.push(handle.join().unwrap());
+    }
+    assert_eq!(results, ["5", "done"]);
+}
+
+#[test]
+fn test_tid_map_entry() {
+    use better_any::map::TidMap;
+
+    let s = String::from("name");
+    let mut map = TidMap::new();
+    map.entry::<S1>().or_insert_with(|| S1(1)).0 += 1;
+    map.entry::<S1>().or_insert_with(|| unreachable!()).0 += 1;
+    map.entry::<S2>().or_insert_with(|| S2(&s));
+    *map.entry::<usize>().or_default() += 5;
+    assert_eq!(map.len(), 3);
+    assert_eq!(map.get::<S1>().unwrap().0, 3);
+
+    let mut s1 = 0;
+    let mut s2 = 0;
+    let mut other = 0;
+    for value in &map {
+        if value.is::<S1>() {
+            s1 += 1;
+        } else if value.is::<S2>() {
+            s2 += 1;
+        } else {
+            assert!(value.type_name().ends_with("usize"));
+            other += 1;
+        }
+    }
+    assert_eq!((s1, s2, other), (1, 1, 1));
+
+    for value in map.iter_mut() {
+        if let Some(value) = value.downcast_mut::<usize>() {
+            *value += 1;
+        }
+    }
+    assert_eq!(map.get::<usize>(), Some(&6));
+
+    let boxes: Vec<_> = map.into_iter().collect();
+    assert_eq!(boxes.len(), 3);
+    assert!(boxes.iter().any(|it| (**it).is::<S2>()));
+
+    let mut map = TidMap::new();
+    map.insert(S1(1));
+    assert!(!map.is_empty());
+    map.clear();
+    assert!(map.is_empty());
+}
+
+#[test]
+fn test_name_registry() {
+    use better_any::registry::NameRegistry;
+
+    let mut registry = NameRegistry::new();
+    registry.register::<S1>("first");
+    registry.register::<S2>("second");
+
+    let s = String::from("test");
+    let values: Vec<Box<dyn Tid>> = vec![Box::new(S1(1)), Box::new(S2(&s)), Box::new(5usize)];
+    let names: Vec<_> = values.iter().map(|it| registry.name_of(&**it)).collect();
+    assert_eq!(names, [Some("first"), Some("second"), None]);
+    assert_eq!(registry.name_of(<&dyn Tid>::from(&S1(1))), Some("first"));
+}
+
+#[test]
+fn test_fn_pointers() {
+    fn double(x: u8) -> u8 {
+        x * 2
+    }
+    fn narrow(x: u16) -> u8 {
+        x as u8
+    }
+    fn len(s: S2<'_>) -> usize {
+        s.0.len()
+    }
+    // elided lifetimes make function pointer higher-ranked, which is not `Tid`
+    fn table<'a>() -> Vec<Box<dyn Tid<'a> + 'a>> {
+        vec![
+            Box::new(double as fn(u8) -> u8),
+            Box::new(narrow as fn(u16) -> u8),
+            Box::new(len as fn(S2<'a>) -> usize),
+        ]
+    }
+    fn call_len<'a>(table: &[Box<dyn Tid<'a> + 'a>], s: &'a str) -> usize {
+        (*table[2]).downcast_ref::<fn(S2<'a>) -> usize>().unwrap()(S2(s))
+    }
+
+    let s = String::from("test");
+    let table = table();
+    let double = (*table[0]).downcast_ref::<fn(u8) -> u8>().unwrap();
+    assert_eq!(double(2), 4);
+    assert!((*table[0]).downcast_ref::<fn(u16) -> u8>().is_none());
+    assert!((*table[1]).downcast_ref::<fn(u8) -> u8>().is_none());
+    assert_eq!((*table[1]).downcast_ref::<fn(u16) -> u8>().unwrap()(258), 2);
+    assert_eq!(call_len(&table, &s), 4);
+
+    assert_ne!(<fn(u8, u16)>::id(), <fn(u16, u8)>::id());
+    assert_ne!(<fn(u8)>::id(), <fn(u8, u8)>::id());
+    assert_ne!(<fn() -> u8>::id(), <(u8,)>::id());
+}
+
+#[test]
+fn test_downcast_ref_checked() {
+    use better_any::TypeMismatch;
+
+    fn describe<'a>(value: &(dyn Tid<'a> + 'a)) -> String {
+        match value.downcast_ref_checked::<S1>() {
+            Ok(s1) => format!("S1({})", s1.0),
+            Err(TypeMismatch { actual_name, .. }) => {
+                format!("type mismatch: found {}", actual_name)
+            }
+        }
+    }
+
+    let s = String::from("test");
+    assert_eq!(describe(&S1(3)), "S1(3)");
+    assert!(describe(&S2(&s)).ends_with("S2<'_>"));
+    let err = (&S2(&s) as &dyn Tid)
+        .downcast_ref_checked::<S1>()
+        .err()
+        .unwrap();
+    assert_eq!((err.expected, err.actual), (S1::id(), S2::id()));
+    let err: Box<dyn std::error::Error> = Box::new(err);
+    assert!(err.to_string().contains("S1"));
+}
+
+#[test]
+fn test_downcast_array_slice() {
+    let array: Box<dyn Tid> = Box::new([S1(1), S1(2), S1(3)]);
+    let slice = array.downcast_array_slice::<S1, 3>().unwrap();
+    assert_eq!(slice.iter().map(|it| it.0).sum::<usize>(), 6);
+    assert!(array.downcast_array_slice::<S1, 4>().is_none());
+    assert!(array.downcast_array_slice::<usize, 3>().is_none());
+}
+
+#[test]
+fn test_derive_where_clause() {
+    #[derive(Tid)]
+    struct Items<'a, I>(&'a [I::Item], I)
+    where
+        I: Iterator,
+        I::Item: Copy + 'a;
+
+    let data = [1u8, 2];
+    let items = Items(&data, IntoIterator::into_iter([3u8]));
+    let value = &items as &dyn Tid;
+    let items = value
+        .downcast_ref::<Items<std::array::IntoIter<u8, 1>>>()
+        .unwrap();
+    assert_eq!(items.0, [1, 2]);
+    assert_eq!(items.1.clone().next(), Some(3));
+}
+
+#[test]
+fn test_downcast_cell_get() {
+    use std::cell::Cell;
+
+    let counter = Cell::new(1i32);
+    let values: [&dyn Tid; 2] = [&counter, &S1(0)];
+    counter.set(counter.get() + 1);
+    assert_eq!(values[0].downcast_cell_get::<i32>(), Some(2));
+    assert_eq!(values[0].downcast_cell_get::<i64>(), None);
+    assert_eq!(values[1].downcast_cell_get::<i32>(), None);
+}
+
+#[test]
+fn test_borrowed_in_option_result() {
+    fn erase<'a>(text: &'a str, bytes: &'a [u8]) -> Vec<Box<dyn Tid<'a> + 'a>> {
+        vec![
+            Box::new(Some(text)),
+            Box::new(Ok::<_, S1>(S2(text))),
+            Box::new(Err::<&[u8], S2>(S2(text))),
+            Box::new(vec![Ok::<_, S1>(bytes)]),
+        ]
+    }
+
+    let s = String::from("test");
+    let values = erase(&s, s.as_bytes());
+    assert_eq!(
+        *(*values[0]).downcast_ref::<Option<&str>>().unwrap(),
+        Some("test")
+    );
+    assert!((*values[0]).downcast_ref::<Option<&[u8]>>().is_none());
+    let result = (*values[1]).downcast_ref::<Result<S2, S1>>().unwrap();
+    assert_eq!(result.as_ref().ok().unwrap().0, "test");
+    assert!((*values[1]).downcast_ref::<Result<S1, S2>>().is_none());
+    let result = (*values[2]).downcast_ref::<Result<&[u8], S2>>().unwrap();
+    assert_eq!(result.as_ref().err().unwrap().0, "test");
+    let results = (*values[3])
+        .downcast_ref::<Vec<Result<&[u8], S1>>>()
+        .unwrap();
+    assert_eq!(results[0].as_ref().ok().unwrap(), b"test");
+}
+
+#[test]
+fn test_any_ref_bridge() {
+    let value = S1(5);
+    let any = &value as &dyn Any;
+    let tid: Box<dyn Tid> = any.into();
+    assert_eq!(tid.downcast_any_ref::<S1>().unwrap().0, 5);
+    assert!((*tid).downcast_ref::<S1>().is_none());
+    assert!(tid.downcast_any_ref::<usize>().is_none());
+    assert!((*tid).type_name().contains("Any"));
+
+    let direct: &dyn Tid = (&value).into();
+    assert_eq!(direct.downcast_any_ref::<S1>().unwrap().0, 5);
+    assert_ne!(direct.self_id(), (*tid).self_id());
+}
+
+#[test]
+fn test_arc_tid_map() {
+    use better_any::map::ArcTidMap;
+    use std::sync::Arc;
+
+    let s = String::from("shared");
+    let mut map = ArcTidMap::new();
+    map.insert_arc(Arc::new(S2(&s)));
+    assert!(map.insert_arc(Arc::new(S1(1))).is_none());
+    assert_eq!(map.insert_arc(Arc::new(S1(2))).unwrap().0, 1);
+    assert_eq!(map.len(), 2);
+
+    let map = &map;
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..2)
+            .map(|_| scope.spawn(move || (map.get::<S1>().unwrap().0, map.get::<S2>().unwrap().0)))
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), (2, "shared"));
+        }
+    });
+    assert_eq!(Arc::strong_count(&map.get::<S1>().unwrap()), 2);
+}
+
+#[test]
+fn test_tid_transparent() {
+    use better_any::TidTransparent;
+
+    #[derive(Tid, TidTransparent)]
+    #[repr(transparent)]
+    struct Meters(f64);
+
+    #[derive(Tid, TidTransparent)]
+    #[repr(transparent)]
+    struct Label<'a>(S2<'a>);
+
+    let mut value: Box<dyn Tid> = Box::new(Meters(1.5));
+    assert_eq!((*value).downcast_ref::<Meters>().unwrap().0, 1.5);
+    *(*value).downcast_transparent_mut::<Meters>().unwrap() += 1.0;
+    assert_eq!((*value).downcast_transparent_ref::<Meters>(), Some(&2.5));
+    assert!((*value).downcast_ref::<f64>().is_none());
+    let value = value.downcast_transparent_box::<Meters>().ok().unwrap();
+    assert_eq!(*value, 2.5);
+
+    let s = String::from("label");
+    let label = &Label(S2(&s)) as &dyn Tid;
+    assert_eq!(
+        label.downcast_transparent_ref::<Label>().unwrap().0,
+        "label"
+    );
+    assert!(label.downcast_transparent_ref::<Meters>().is_none());
+}
+
+#[test]
+fn test_downcast_arc_peek() {
+    use std::sync::Arc;
+
+    let s = String::from("cached");
+    let shared: Arc<dyn Tid> = Arc::new(S2(&s));
+    let entries = [shared.clone(), Arc::new(S1(1)), shared.clone()];
+    let mut found = Vec::new();
+    for entry in &entries {
+        if TidExt::downcast_arc_peek::<S2>(entry) {
+            found.push(unsafe { entry.clone().downcast_arc_unchecked::<S2>() });
+        }
+    }
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0].0, "cached");
+    assert!(!TidExt::downcast_arc_peek::<S1>(&shared));
+    assert_eq!(Arc::strong_count(&shared), 5);
+    assert_eq!(Arc::weak_count(&shared), 0);
+}
+
+#[test]
+fn test_downcast_short_object_lifetime() {
+    fn get<'b, 'a>(value: &'b (dyn Tid<'a> + 'b)) -> Option<&'b S2<'a>> {
+        value.downcast_ref()
+    }
+    fn get_box<'b, 'a>(value: Box<dyn Tid<'a> + 'b>) -> Option<Box<S2<'a>>> {
+        value.downcast_box().ok()
+    }
+
+    let s = String::from("long");
+    let value = S2(&s);
+    let short = get(&value).unwrap();
+    assert_eq!(short.0, "long");
+    assert_eq!(value.0, "long");
+    let boxed: Box<dyn Tid + '_> = Box::new(S2(&s));
+    assert_eq!(get_box(boxed).unwrap().0, "long");
+}
+
+#[test]
+fn test_hash_map_iter() {
+    use std::collections::hash_map::{IntoIter, Iter};
+    use std::collections::HashMap;
+
+    let s = String::from("value");
+    let mut map = HashMap::new();
+    map.insert(1u32, S2(&s));
+    {
+        let iter = map.iter();
+        let erased: &dyn Tid = &iter;
+        assert!(!erased.is::<IntoIter<u32, S2>>());
+        let (key, value) = erased
+            .downcast_ref::<Iter<u32, S2>>()
+            .unwrap()
+            .clone()
+            .next()
+            .unwrap();
+        assert_eq!((*key, value.0), (1, "value"));
+    }
+    let erased: Box<dyn Tid> = Box::new(map.into_iter());
+    let mut iter = erased.downcast_box::<IntoIter<u32, S2>>().ok().unwrap();
+    let (key, value) = iter.next().unwrap();
+    assert_eq!((key, value.0), (1, "value"));
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn test_atomics() {
+    use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+
+    let counter = AtomicU32::new(1);
+    let erased: &dyn Tid = &counter;
+    assert!(!erased.is::<AtomicUsize>());
+    assert!(!erased.is::<u32>());
+    erased
+        .downcast_ref::<AtomicU32>()
+        .unwrap()
+        .fetch_add(2, Ordering::Relaxed);
+    assert_eq!(counter.load(Ordering::Relaxed), 3);
+
+    let flag = AtomicBool::new(false);
+    let erased: &dyn Tid = &flag;
+    erased
+        .downcast_ref::<AtomicBool>()
+        .unwrap()
+        .store(true, Ordering::Relaxed);
+    assert!(flag.load(Ordering::Relaxed));
+}
+
+#[test]
+fn test_find_source_downcast() {
+    use better_any::find_source_downcast;
+    use std::error::Error;
+    use std::fmt;
+
+    #[derive(Debug)]
+    struct Leaf(usize);
+    impl fmt::Display for Leaf {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "leaf {}", self.0)
+        }
+    }
+    impl Error for Leaf {}
+
+    #[derive(Debug)]
+    struct Middle(Leaf);
+    impl fmt::Display for Middle {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("middle")
+        }
+    }
+    impl Error for Middle {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+
+    #[derive(Debug)]
+    struct Outer<'a>(&'a str, Middle);
+    impl fmt::Display for Outer<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(self.0)
+        }
+    }
+    impl Error for Outer<'_> {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            Some(&self.1)
+        }
+    }
+
+    let s = String::from("outer");
+    let err = Outer(&s, Middle(Leaf(5)));
+    assert_eq!(find_source_downcast::<Leaf>(&err).unwrap().0, 5);
+    assert!(find_source_downcast::<Middle>(&err).is_some());
+    assert!(find_source_downcast::<Leaf>(&Leaf(1)).is_none());
+    assert!(find_source_downcast::<std::io::Error>(&err).is_none());
+}
+
+#[test]
+fn test_clone_tid() {
+    use better_any::clone::CloneTid;
+
+    #[derive(Clone, Tid)]
+    struct Shared<'a>(&'a str, usize);
+
+    let s = String::from("proto");
+    let prototype: Box<dyn CloneTid> = Box::new(Shared(&s, 1));
+    let mut copy = prototype.clone();
+    copy.downcast_mut::<Shared>().unwrap().1 = 2;
+    assert!(copy.downcast_ref::<S2>().is_none());
+    let original = prototype.downcast_box::<Shared>().ok().unwrap();
+    let copy = copy.downcast_box::<Shared>().ok().unwrap();
+    assert_eq!((original.0, original.1), ("proto", 1));
+    assert_eq!((copy.0, copy.1), ("proto", 2));
+}
+
+#[test]
+fn test_project_field() {
+    use better_any::project_field;
+    use std::rc::Rc;
+
+    #[derive(Tid)]
+    struct Parts<'a> {
+        name: S2<'a>,
+        counter: Rc<()>,
+    }
+
+    let s = String::from("field");
+    let counter = Rc::new(());
+    let value: Box<dyn Tid> = Box::new(Parts {
+        name: S2(&s),
+        counter: counter.clone(),
+    });
+    assert_eq!(Rc::strong_count(&counter), 2);
+    let name = project_field!(value, Parts => name).ok().unwrap();
+    assert_eq!(name.0, "field");
+    assert_eq!(Rc::strong_count(&counter), 1);
+
+    let value: Box<dyn Tid> = Box::new(Parts {
+        name: S2(&s),
+        counter: counter.clone(),
+    });
+    let projected = project_field!(value, Parts => counter).ok().unwrap();
+    assert!(Rc::ptr_eq(&projected, &counter));
+    assert_eq!(Rc::strong_count(&counter), 2);
+
+    let value: Box<dyn Tid> = Box::new(S3(&name));
+    let value = project_field!(value, Parts => name).err().unwrap();
+    let inner = project_field!(value, S3<S2> => 0).ok().unwrap();
+    assert_eq!(inner.0, "field");
+}
+
+#[test]
+fn test_derive_provider() {
+    use better_any::provider::{request_ref, Demand, Provider, ProviderExt};
+
+    #[derive(Tid, Provider)]
+    struct Parts<'a, T> {
+        #[provide]
+        name: S2<'a>,
+        #[provide(value)]
+        extra: Option<&'a T>,
+        #[provide(value)]
+        len: usize,
+        #[allow(dead_code)]
+        hidden: u8,
+    }
+
+    #[derive(Tid, Provider)]
+    struct Tuple(#[provide] S1, u16);
+
+    let s = String::from("parts");
+    let parts = Parts {
+        name: S2(&s),
+        extra: Some(&5u32),
+        len: 2,
+        hidden: 1,
+    };
+    assert_eq!(parts.request_ref::<S2>().unwrap().0, "parts");
+    assert_eq!(parts.request_value::<Option<&u32>>(), Some(Some(&5)));
+    assert_eq!(parts.request_value::<usize>(), Some(2));
+    assert!(parts.request_ref::<usize>().is_none());
+    assert!(parts.request_ref::<u8>().is_none());
+
+    let tuple = Tuple(S1(4), 7);
+    assert_eq!(request_ref::<S1>(&tuple).unwrap().0, 4);
+    assert_eq!(tuple.1, 7);
+    assert!(tuple.request_ref::<u16>().is_none());
+}
+
+#[test]
+fn test_downcast_shim() {
+    use better_any::downcast::DowncastTid;
+    use better_any::impl_downcast_tid;
+
+    trait Node<'a>: DowncastTid<'a> {
+        fn label(&self) -> &'a str;
+    }
+    impl_downcast_tid!(Node<'a>);
+    impl<'a> Node<'a> for S2<'a> {
+        fn label(&self) -> &'a str {
+            self.0
+        }
+    }
+
+    trait Plugin: DowncastTid<'static> {}
+    impl_downcast_tid!(Plugin);
+    impl Plugin for S1 {}
+
+    let s = String::from("node");
+    let mut node: Box<dyn Node> = Box::new(S2(&s));
+    assert!(node.is::<S2>());
+    assert!(!node.is::<S1>());
+    node.downcast_mut::<S2>().unwrap().0 = "changed";
+    assert_eq!(node.label(), "changed");
+    assert!(node.as_tid().downcast_ref::<S2>().is_some());
+    let node = node.downcast::<S1>().err().unwrap();
+    let node = node.downcast::<S2>().ok().unwrap();
+    assert_eq!(node.0, "changed");
+
+    let plugin: Box<dyn Plugin> = Box::new(S1(3));
+    assert_eq!(plugin.downcast_ref::<S1>().unwrap().0, 3);
+    let erased = plugin.into_tid();
+    assert_eq!(erased.downcast_box::<S1>().ok().unwrap().0, 3);
+}
+
+#[test]
+fn test_reverse_wrapping() {
+    use std::cmp::Reverse;
+    use std::num::Wrapping;
+
+    let erased: &dyn Tid = &Reverse(S1(2));
+    assert!(!erased.is::<S1>());
+    assert!(!erased.is::<Wrapping<S1>>());
+    assert_eq!((erased.downcast_ref::<Reverse<S1>>().unwrap().0).0, 2);
+
+    let s = String::from("key");
+    let erased: &dyn Tid = &Reverse(S2(&s));
+    assert_eq!((erased.downcast_ref::<Reverse<S2>>().unwrap().0).0, "key");
+
+    let erased: &dyn Tid = &Wrapping(5u32);
+    assert!(!erased.is::<Reverse<u32>>());
+    #[cfg(feature = "saturating")]
+    assert!(!erased.is::<std::num::Saturating<u32>>());
+    assert_eq!(erased.downcast_ref::<Wrapping<u32>>(), Some(&Wrapping(5)));
+}
+
+#[test]
+fn test_downcast_box_or() {
+    let s = String::from("fallback");
+    let value: Box<dyn Tid> = Box::new(S1(4));
+    let value = value.downcast_box_or(|_| Box::new(S2(&s)));
+    assert_eq!(value.0, "fallback");
+
+    let value: Box<dyn Tid> = Box::new(S2("direct"));
+    let value = value.downcast_box_or::<S2, _>(|_| unreachable!());
+    assert_eq!(value.0, "direct");
+
+    let value: Box<dyn Tid> = Box::new(S1(4));
+    let value = value.downcast_box_or(|it| Box::new(S2((*it).type_name())));
+    assert!(value.0.ends_with("S1"));
+}
+
+#[test]
+fn test_pin_mut_ref() {
+    use std::marker::PhantomPinned;
+    use std::pin::Pin;
+
+    #[derive(Tid)]
+    struct Fut(usize, PhantomPinned);
+
+    let mut fut = Box::pin(Fut(1, PhantomPinned));
+    {
+        let erased: Box<dyn Tid + '_> = Box::new(fut.as_mut());
+        assert!(!erased.is::<Pin<&mut S1>>());
+        assert!(!erased.is::<&mut Fut>());
+        let pinned = *erased.downcast_box::<Pin<&mut Fut>>().ok().unwrap();
+        unsafe { pinned.get_unchecked_mut().0 += 1 };
+    }
+    assert_eq!(fut.0, 2);
+}
+
+#[test]
+fn test_tid_macro_const_params() {
+    struct Ring<'a, T, const N: usize, const WRAP: bool>(&'a [T; N]);
+    tid! { impl<'a, T, const N: usize, const WRAP: bool> TidAble<'a> for Ring<'a, T, N, WRAP> }
+
+    let s = String::from("ring");
+    let items = [S2(&s), S2(&s)];
+    let value = &Ring::<_, 2, true>(&items) as &dyn Tid;
+    assert!(!value.is::<Ring<S2, 2, false>>());
+    assert!(!value.is::<Ring<S2, 3, true>>());
+    assert!(!value.is::<Ring<S1, 2, true>>());
+    assert_eq!(
+        value.downcast_ref::<Ring<S2, 2, true>>().unwrap().0[1].0,
+        "ring"
+    );
+}
+
+#[test]
+fn test_capability_registry() {
+    use better_any::registry::CapabilityRegistry;
+    use better_any::typeid_of;
+
+    trait Render {}
+    tid! { impl<'a> TidAble<'a> for dyn Render + 'a }
+    trait Persist {}
+    tid! { impl<'a> TidAble<'a> for dyn Persist + 'a }
+    trait Network {}
+    tid! { impl<'a> TidAble<'a> for dyn Network + 'a }
+
+    let mut registry = CapabilityRegistry::new();
+    registry.register::<S2<'static>, dyn Render>();
+    registry.register::<S2<'static>, dyn Persist>();
+    registry.register::<S2<'static>, dyn Render>();
+    registry.register::<S1, dyn Network>();
+
+    let s = String::from("plugin");
+    let value = S2(&s);
+    let capabilities = registry.supertraits_of(&value).collect::<Vec<_>>();
+    assert_eq!(
+        capabilities,
+        [typeid_of::<dyn Render>(), typeid_of::<dyn Persist>()]
+    );
+    assert!(registry.has::<dyn Persist>(&value));
+    assert!(!registry.has::<dyn Network>(&value));
+
+    let bridged = <&dyn Tid>::from(&S1(0));
+    assert!(registry.has::<dyn Network>(bridged));
+    assert_eq!(registry.supertraits_of(&5usize).count(), 0);
+}
+
+#[cfg(feature = "downcast-rs")]
+#[test]
+fn test_from_downcast() {
+    use better_any::downcast::from_downcast;
+    use downcast_rs::{impl_downcast, Downcast};
+
+    trait Component: Downcast {
+        fn name(&self) -> &'static str;
+    }
+    impl_downcast!(Component);
+
+    struct Position(i32);
+    impl Component for Position {
+        fn name(&self) -> &'static str {
+            "position"
+        }
+    }
+
+    let components: Vec<Box<dyn Component>> = vec![Box::new(Position(3))];
+    let value = from_downcast(&*components[0]);
+    assert!((*value).downcast_any_ref::<u8>().is_none());
+    assert_eq!((*value).downcast_any_ref::<Position>().unwrap().0, 3);
+    assert_eq!(components[0].name(), "position");
+}
+
+#[test]
+fn test_downcast_error_propagation() {
+    use better_any::TidDowncastError;
+
+    fn parse<'a>(
+        value: Box<dyn Tid<'a> + 'a>,
+    ) -> Result<usize, TidDowncastError<Box<dyn Tid<'a> + 'a>>> {
+        let value = value.downcast::<S1>()?;
+        Ok(value.0)
+    }
+
+    assert_eq!(parse(Box::new(S1(1))).ok(), Some(1));
+    let s = String::from("wrong");
+    let err = parse(Box::new(S2(&s))).err().unwrap();
+    let message = err.to_string();
+    assert!(message.contains("S1"), "{}", message);
+    assert!(message.contains("S2"), "{}", message);
+    assert_eq!(err.value.downcast::<S2>().ok().unwrap().0, "wrong");
+}
+
+#[better_any::tid_closed_set]
+enum ClosedSet<'a> {
+    First(S1),
+    Second(S2<'a>),
+}
+
+#[test]
+fn test_closed_set() {
+    fn describe(value: Box<dyn Tid<'_> + '_>) -> String {
+        match ClosedSet::downcast(value) {
+            Ok(ClosedSet::First(s1)) => s1.0.to_string(),
+            Ok(ClosedSet::Second(s2)) => s2.0.to_owned(),
+            Err(value) => format!("unknown {}", value.type_name()),
+        }
+    }
+
+    let s = String::from("second");
+    assert_eq!(describe(ClosedSet::First(S1(1)).erase()), "1");
+    assert_eq!(describe(ClosedSet::Second(S2(&s)).erase()), "second");
+    assert!(describe(Box::new(S4(5usize))).starts_with("unknown"));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serialize_registry() {
+    use better_any::serialize::TidRegistry;
+    use std::borrow::Cow;
+
+    let mut registry = TidRegistry::new();
+    registry.register::<Cow<str>>("label");
+    registry.register::<u32>("count");
+
+    let s = String::from("borrowed");
+    let label: Cow<str> = Cow::Borrowed(&s);
+    assert_eq!(registry.name_of(&label), Some("label"));
+    let values: Vec<Box<dyn Tid>> = vec![Box::new(label), Box::new(3u32)];
+    let tagged = values
+        .iter()
+        .map(|it| registry.tagged(&**it))
+        .collect::<Vec<_>>();
+    let json = serde_json::to_string(&tagged).unwrap();
+    assert_eq!(json, r#"[{"label":"borrowed"},{"count":3}]"#);
+
+    let mut deserializer = serde_json::Deserializer::from_str(r#"{"label":"owned"}"#);
+    let value = registry.deserialize_tid(&mut deserializer).unwrap();
+    assert_eq!((*value).downcast_ref::<Cow<str>>().unwrap(), "owned");
+
+    let err = serde_json::to_string(&registry.tagged(&S1(1)))
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("is not registered"));
+    let mut deserializer = serde_json::Deserializer::from_str(r#"{"missing":1}"#);
+    let err = registry.deserialize_tid(&mut deserializer).err().unwrap();
+    assert!(err.to_string().contains("type `missing` is not registered"));
+}
+
+mod result_alias {
+    use super::{S1, S2};
+    use better_any::tid_enum_dispatch;
+
+    #[allow(dead_code)]
+    type Result<T> = std::result::Result<T, ()>;
+
+    enum Value<'a> {
+        Id(S1),
+        Name(S2<'a>),
+    }
+    tid_enum_dispatch!(Value<'a> { S1 => Id, S2<'a> => Name });
+
+    #[better_any::tid_closed_set]
+    enum ClosedSet<'a> {
+        Id(S1),
+        Name(S2<'a>),
+    }
+
+    #[test]
+    fn test_closed_set_with_result_alias() {
+        let s = String::from("name");
+        match ClosedSet::downcast(ClosedSet::Name(S2(&s)).erase()) {
+            Ok(ClosedSet::Name(name)) => assert_eq!(name.0, "name"),
+            _ => panic!("wrong variant"),
+        }
+        match ClosedSet::downcast(ClosedSet::Id(S1(3)).erase()) {
+            Ok(ClosedSet::Id(id)) => assert_eq!(id.0, 3),
+            _ => panic!("wrong variant"),
+        }
+    }
+
+    trait Shape: better_any::downcast::DowncastTid<'static> {}
+    better_any::impl_downcast_tid!(Shape);
+    impl Shape for S1 {}
+
+    #[test]
+    fn test_impl_downcast_tid_with_result_alias() {
+        let shape: Box<dyn Shape> = Box::new(S1(2));
+        assert_eq!(shape.downcast::<S1>().ok().unwrap().0, 2);
+    }
+
+    #[test]
+    fn test_tid_enum_dispatch_with_result_alias() {
+        assert!(matches!(
+            Value::from_tid_box(Box::new(S1(1))),
+            Ok(Value::Id(S1(1)))
+        ));
+        let s = String::from("name");
+        match Value::from_tid_box(Box::new(S2(&s))) {
+            Ok(Value::Name(name)) => assert_eq!(name.0, "name"),
+            _ => panic!("wrong variant"),
+        }
+        assert!(Value::from_tid_box(Box::new(5usize)).is_err());
+    }
+}
+
+#[test]
+fn test_any_origin_is_not_tid_origin() {
+    let any: Box<dyn Any> = Box::new(S1(5));
+    let tid = better_any::into_tid_box(any);
+    assert!(tid.downcast_ref::<S1>().is_none());
+    assert_eq!(tid.downcast_any_ref::<S1>().unwrap().0, 5);
+
+    let tid: Box<dyn Tid> = Box::new(S1(6)).into();
+    assert!(tid.downcast_ref::<S1>().is_none());
+    assert_eq!(tid.downcast_any_ref::<S1>().unwrap().0, 6);
+
+    let value = S1(7);
+    let tid: Box<dyn Tid> = (&value as &dyn Any).into();
+    assert!(tid.downcast_ref::<S1>().is_none());
+    assert_eq!(tid.downcast_any_ref::<S1>().unwrap().0, 7);
+}
+
+#[test]
+fn test_dispatch_cache_nested_resolve() {
+    use better_any::dispatch::DispatchCache;
+
+    let s = String::from("outer");
+    let inner = S1(1);
+    let outer = S2(&s);
+    let cache = DispatchCache::new();
+    let resolved = cache.resolve(&outer, |_| {
+        let inner = cache.resolve(&inner, |it| it.type_name().len());
+        inner + 1
+    });
+    assert_eq!(resolved, cache.resolve(&inner, |_| unreachable!()) + 1);
+    assert_eq!(cache.resolve(&outer, |_| unreachable!()), resolved);
+    assert_eq!(cache.len(), 2);
+}
+
+#[test]
+fn test_registries_into_tid_box() {
+    use better_any::registry::{CapabilityRegistry, NameRegistry, ProvenanceAwareRegistry};
+
+    trait Draw {}
+    tid! { impl<'a> TidAble<'a> for dyn Draw + 'a }
+
+    let mut provenance = ProvenanceAwareRegistry::new();
+    let s1 = provenance.register::<S1>();
+    let mut names = NameRegistry::new();
+    names.register::<S1>("S1");
+    let mut capabilities = CapabilityRegistry::new();
+    capabilities.register::<S1, dyn Draw>();
+
+    let tid = better_any::into_tid_box(Box::new(S1(5)));
+    assert_eq!(provenance.lookup(&*tid), Some(s1));
+    assert_eq!(names.name_of(&*tid), Some("S1"));
+    assert!(capabilities.has::<dyn Draw>(&*tid));
+
+    let tid = better_any::into_tid_box(Box::new(5usize));
+    assert_eq!(provenance.lookup(&*tid), None);
+    assert_eq!(names.name_of(&*tid), None);
+    assert!(!capabilities.has::<dyn Draw>(&*tid));
+}
+
+#[test]
+fn test_registries_any_ref() {
+    use better_any::registry::{CapabilityRegistry, NameRegistry, ProvenanceAwareRegistry};
+
+    trait Draw {}
+    tid! { impl<'a> TidAble<'a> for dyn Draw + 'a }
+
+    let mut provenance = ProvenanceAwareRegistry::new();
+    let s1 = provenance.register::<S1>();
+    let mut names = NameRegistry::new();
+    names.register::<S1>("S1");
+    let mut capabilities = CapabilityRegistry::new();
+    capabilities.register::<S1, dyn Draw>();
+
+    let value = S1(5);
+    let tid: Box<dyn Tid> = (&value as &dyn Any).into();
+    assert_eq!(provenance.lookup(&*tid), Some(s1));
+    assert_eq!(names.name_of(&*tid), Some("S1"));
+    assert!(capabilities.has::<dyn Draw>(&*tid));
+
+    let value = 5usize;
+    let tid: Box<dyn Tid> = (&value as &dyn Any).into();
+    assert_eq!(provenance.lookup(&*tid), None);
+    assert_eq!(names.name_of(&*tid), None);
+    assert!(!capabilities.has::<dyn Draw>(&*tid));
+}
+
+#[test]
+fn test_static_phantom_marker() {
+    use std::marker::PhantomData;
+
+    #[derive(Tid)]
+    struct Quantity<'a, U: 'static>(&'a f64, PhantomData<U>);
+    struct Meters;
+    struct Seconds;
+
+    let x = 2.5;
+    let meters = Quantity::<Meters>(&x, PhantomData);
+    let erased = &meters as &dyn Tid;
+    assert_ne!(Quantity::<Meters>::id(), Quantity::<Seconds>::id());
+    assert!(erased.downcast_ref::<Quantity<Seconds>>().is_none());
+    assert_eq!(*erased.downcast_ref::<Quantity<Meters>>().unwrap().0, 2.5);
+}
+
+#[test]
+fn test_union() {
+    #[derive(Tid)]
+    #[repr(C)]
+    union Value<'a, T: Copy> {
+        ptr: &'a T,
+        int: usize,
+    }
+
+    let x = 5u32;
+    let value = Value { ptr: &x };
+    let erased = &value as &dyn Tid;
+    assert!(erased.downcast_ref::<Value<u64>>().is_none());
+    let value = erased.downcast_ref::<Value<u32>>().unwrap();
+    assert_eq!(unsafe { *value.ptr }, 5);
+    let value = Value::<u32> { int: 7 };
+    assert_eq!(
+        unsafe {
+            (&value as &dyn Tid)
+                .downcast_ref::<Value<u32>>()
+                .unwrap()
+                .int
+        },
+        7
+    );
+}
+
+#[test]
+fn test_is_any() {
+    let a = S1(5);
+    let from_ref: &dyn Tid = (&a).into();
+    assert!(from_ref.is_any::<S1>());
+    assert!(!from_ref.is_any::<usize>());
+
+    let from_box: Box<dyn Tid> = Box::new(S1(5)).into();
+    assert!(from_box.is_any::<S1>());
+    let from_any_box = better_any::into_tid_box(Box::new(5usize));
+    assert!(from_any_box.is_any::<usize>());
+    let any = &a as &dyn Any;
+    let from_any_ref: Box<dyn Tid> = any.into();
+    assert!(from_any_ref.is_any::<S1>());
+
+    let direct = &a as &dyn Tid;
+    assert!(!direct.is_any::<S1>());
+    assert!(direct.is::<S1>());
+}
+
+#[test]
+fn test_dispatch_cache_zero_sized() {
+    use better_any::dispatch::DispatchCache;
+
+    struct Unit;
+    tid!(Unit);
+
+    let (first, second) = (Unit, Unit);
+    let cache = DispatchCache::new();
+    assert_eq!(cache.resolve(&first, |_| 1), 1);
+    assert_eq!(cache.resolve(&second, |_| 2), 2);
+    assert!(cache.is_empty());
+}
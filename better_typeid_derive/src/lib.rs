@@ -1,14 +1,16 @@
 extern crate proc_macro;
 use proc_macro::TokenStream;
 
-use quote::quote;
 use quote::ToTokens;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
 use syn::{parse2, Generics, Type, TypeParamBound};
 
 use proc_macro2::Ident;
+use syn::visit_mut::VisitMut;
 use syn::{
-    parse_macro_input, ConstParam, DeriveInput, GenericParam, ItemImpl, Lifetime, LifetimeDef,
-    TypeParam,
+    parse_macro_input, Attribute, ConstParam, Data, DataStruct, DeriveInput, Fields, GenericParam,
+    ItemEnum, ItemImpl, Lifetime, LifetimeDef, Meta, NestedMeta, TypeParam,
 };
 
 // struct RenameLifetimeVisitor;
@@ -63,11 +65,56 @@ fn is_static(bound: &TypeParamBound) -> bool {
     }
 }
 
-#[proc_macro_derive(Tid)]
+// renames given lifetimes to a single one
+struct UnifyLifetimes {
+    from: Vec<Lifetime>,
+    to: Lifetime,
+}
+
+impl VisitMut for UnifyLifetimes {
+    fn visit_lifetime_mut(&mut self, i: &mut Lifetime) {
+        if self.from.contains(i) {
+            *i = self.to.clone();
+        }
+    }
+}
+
+#[derive(Default)]
+struct Options {
+    unify_lifetimes: bool,
+}
+
+fn parse_options(attrs: &[Attribute]) -> syn::Result<Options> {
+    let mut options = Options::default();
+    for attr in attrs.iter().filter(|it| it.path.is_ident("tid")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(syn::Error::new_spanned(meta, "expected `#[tid(...)]`")),
+        };
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("unify_lifetimes") => {
+                    options.unify_lifetimes = true
+                }
+                nested => return Err(syn::Error::new_spanned(nested, "unknown `tid` option")),
+            }
+        }
+    }
+    Ok(options)
+}
+
+#[proc_macro_derive(Tid, attributes(tid))]
 pub fn my_derive(input: TokenStream) -> TokenStream {
     let DeriveInput {
-        ident, generics, ..
+        attrs,
+        ident,
+        mut generics,
+        ..
     } = parse_macro_input!(input as DeriveInput);
+    let options = match parse_options(&attrs) {
+        Ok(options) => options,
+        Err(err) => return err.to_compile_error().into(),
+    };
 
     let type_params = generics
         .params
@@ -83,16 +130,327 @@ pub fn my_derive(input: TokenStream) -> TokenStream {
     } else {
         quote! { #ident<#(#type_params),*> }
     };
-    let type_ = parse2(type_).unwrap();
+    let mut type_ = parse2(type_).unwrap();
+
+    if options.unify_lifetimes {
+        let mut lifetimes = generics.lifetimes().map(|it| it.lifetime.clone());
+        if let Some(to) = lifetimes.next() {
+            let from = lifetimes.collect::<Vec<_>>();
+            generics.params = generics
+                .params
+                .into_iter()
+                .filter(|it| match it {
+                    GenericParam::Lifetime(LifetimeDef { lifetime, .. }) => {
+                        !from.contains(lifetime)
+                    }
+                    _ => true,
+                })
+                .collect();
+            let mut visitor = UnifyLifetimes { from, to };
+            visitor.visit_generics_mut(&mut generics);
+            visitor.visit_type_mut(&mut type_);
+        }
+    }
+
     create_impl(generics, Box::new(type_), None).into()
 }
 
+fn is_repr_transparent(attrs: &[Attribute]) -> bool {
+    attrs
+        .iter()
+        .filter(|it| it.path.is_ident("repr"))
+        .any(|attr| {
+            match attr.parse_meta() {
+            Ok(Meta::List(list)) => list.nested.iter().any(|it| {
+                matches!(it, NestedMeta::Meta(Meta::Path(path)) if path.is_ident("transparent"))
+            }),
+            _ => false,
+        }
+        })
+}
+
+#[proc_macro_derive(TidTransparent)]
+pub fn derive_transparent(input: TokenStream) -> TokenStream {
+    let DeriveInput {
+        attrs,
+        ident,
+        mut generics,
+        data,
+        ..
+    } = parse_macro_input!(input as DeriveInput);
+
+    if !is_repr_transparent(&attrs) {
+        return syn::Error::new_spanned(&ident, "`TidTransparent` requires `#[repr(transparent)]`")
+            .to_compile_error()
+            .into();
+    }
+    let inner = match data {
+        Data::Struct(DataStruct { fields, .. }) if fields.len() == 1 => {
+            fields.into_iter().next().unwrap().ty
+        }
+        _ => {
+            return syn::Error::new_spanned(
+                &ident,
+                "`TidTransparent` can be derived only for structs with exactly one field",
+            )
+            .to_compile_error()
+            .into()
+        }
+    };
+
+    let (_, type_generics, _) = generics.split_for_impl();
+    let type_ = quote! { #ident #type_generics };
+    let lifetime = match generics.lifetimes().next() {
+        Some(it) => it.lifetime.clone(),
+        None => {
+            let lifetime: Lifetime = syn::parse2(quote! {'a}).unwrap();
+            generics.params.insert(
+                0,
+                GenericParam::Lifetime(LifetimeDef::new(lifetime.clone())),
+            );
+            lifetime
+        }
+    };
+    {
+        let where_clause = generics.make_where_clause();
+        where_clause
+            .predicates
+            .push(syn::parse2(quote! {#type_: TidAble<#lifetime>}).unwrap());
+        where_clause
+            .predicates
+            .push(syn::parse2(quote! {#inner: #lifetime}).unwrap());
+    }
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    quote! {
+        unsafe impl #impl_generics TidTransparent<#lifetime> for #type_ #where_clause {
+            type Inner = #inner;
+        }
+    }
+    .into()
+}
+
+enum Provide {
+    Ref,
+    Value,
+}
+
+fn parse_provide(attrs: &[Attribute]) -> syn::Result<Option<Provide>> {
+    let mut result = None;
+    for attr in attrs.iter().filter(|it| it.path.is_ident("provide")) {
+        let provide = match attr.parse_meta()? {
+            Meta::Path(_) => Provide::Ref,
+            Meta::List(list)
+                if list.nested.len() == 1
+                    && matches!(&list.nested[0], NestedMeta::Meta(Meta::Path(path)) if path.is_ident("value")) =>
+            {
+                Provide::Value
+            }
+            meta => {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    "expected `#[provide]` or `#[provide(value)]`",
+                ))
+            }
+        };
+        if result.replace(provide).is_some() {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "duplicate `provide` attribute",
+            ));
+        }
+    }
+    Ok(result)
+}
+
+#[proc_macro_derive(Provider, attributes(provide))]
+pub fn derive_provider(input: TokenStream) -> TokenStream {
+    let DeriveInput {
+        ident,
+        mut generics,
+        data,
+        ..
+    } = parse_macro_input!(input as DeriveInput);
+
+    let fields = match data {
+        Data::Struct(DataStruct { fields, .. }) => fields,
+        _ => {
+            return syn::Error::new_spanned(&ident, "`Provider` can be derived only for structs")
+                .to_compile_error()
+                .into()
+        }
+    };
+    if let Some(extra) = generics.lifetimes().nth(1) {
+        return syn::Error::new_spanned(
+            &extra.lifetime,
+            "`Provider` can be derived only for types with at most one lifetime parameter",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let (_, type_generics, _) = generics.split_for_impl();
+    let type_ = quote! { #ident #type_generics };
+    let lifetime = match generics.lifetimes().next() {
+        Some(it) => it.lifetime.clone(),
+        None => {
+            let lifetime: Lifetime = syn::parse2(quote! {'a}).unwrap();
+            generics.params.insert(
+                0,
+                GenericParam::Lifetime(LifetimeDef::new(lifetime.clone())),
+            );
+            lifetime
+        }
+    };
+
+    let mut calls = Vec::new();
+    let mut predicates = vec![quote! {#type_: TidAble<#lifetime>}];
+    for (index, field) in fields.iter().enumerate() {
+        let provide = match parse_provide(&field.attrs) {
+            Ok(Some(provide)) => provide,
+            Ok(None) => continue,
+            Err(err) => return err.to_compile_error().into(),
+        };
+        let member = match &field.ident {
+            Some(ident) => quote! {#ident},
+            None => syn::Index::from(index).to_token_stream(),
+        };
+        let ty = &field.ty;
+        let span = field.ty.span();
+        predicates.push(quote_spanned! {span=> #ty: TidAble<#lifetime>});
+        calls.push(match provide {
+            Provide::Ref => quote! { demand.provide_ref(&self.#member); },
+            Provide::Value => {
+                predicates.push(quote_spanned! {span=> #ty: Clone});
+                quote! { demand.provide_value(|| Clone::clone(&self.#member)); }
+            }
+        });
+    }
+    {
+        let where_clause = generics.make_where_clause();
+        for predicate in predicates {
+            where_clause
+                .predicates
+                .push(syn::parse2(predicate).unwrap());
+        }
+    }
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics Provider<#lifetime> for #type_ #where_clause {
+            fn provide<'__provide>(&'__provide self, demand: &mut Demand<'__provide, #lifetime>) {
+                #(#calls)*
+            }
+        }
+    }
+    .into()
+}
+
+#[proc_macro_attribute]
+pub fn tid_closed_set(_params: TokenStream, input: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(input as ItemEnum);
+    let ident = &item.ident;
+
+    if let Some(extra) = item.generics.lifetimes().nth(1) {
+        return syn::Error::new_spanned(
+            &extra.lifetime,
+            "`tid_closed_set` supports at most one lifetime parameter",
+        )
+        .to_compile_error()
+        .into();
+    }
+    let mut variants: Vec<(&Ident, &Type)> = Vec::new();
+    for variant in &item.variants {
+        match &variant.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                let ty = &fields.unnamed[0].ty;
+                let name = ty.to_token_stream().to_string();
+                if variants
+                    .iter()
+                    .any(|(_, it)| it.to_token_stream().to_string() == name)
+                {
+                    return syn::Error::new_spanned(
+                        ty,
+                        format!("type `{}` is already in the `tid_closed_set`", name),
+                    )
+                    .to_compile_error()
+                    .into();
+                }
+                variants.push((&variant.ident, ty))
+            }
+            _ => {
+                return syn::Error::new_spanned(
+                    variant,
+                    "`tid_closed_set` variants must have exactly one unnamed field",
+                )
+                .to_compile_error()
+                .into()
+            }
+        }
+    }
+
+    let mut generics = item.generics.clone();
+    let (_, type_generics, _) = item.generics.split_for_impl();
+    let lifetime = match generics.lifetimes().next() {
+        Some(it) => it.lifetime.clone(),
+        None => {
+            let lifetime: Lifetime = syn::parse2(quote! {'a}).unwrap();
+            generics.params.insert(
+                0,
+                GenericParam::Lifetime(LifetimeDef::new(lifetime.clone())),
+            );
+            lifetime
+        }
+    };
+    {
+        let where_clause = generics.make_where_clause();
+        for (_, ty) in &variants {
+            let span = ty.span();
+            where_clause.predicates.push(
+                syn::parse2(quote_spanned! {span=> #ty: ::better_any::TidAble<#lifetime>}).unwrap(),
+            );
+        }
+    }
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let names = variants.iter().map(|it| it.0).collect::<Vec<_>>();
+    let types = variants.iter().map(|it| it.1).collect::<Vec<_>>();
+
+    quote! {
+        #item
+
+        impl #impl_generics #ident #type_generics #where_clause {
+            /// Erases value of the variant into `dyn Tid`
+            pub fn erase(self) -> ::better_any::__Box<dyn ::better_any::Tid<#lifetime> + #lifetime> {
+                match self {
+                    #(#ident::#names(value) => ::better_any::__Box::new(value),)*
+                }
+            }
+
+            /// Recovers variant from the type of erased value, returns `value` back if its type is not in the set
+            pub fn downcast(
+                value: ::better_any::__Box<dyn ::better_any::Tid<#lifetime> + #lifetime>,
+            ) -> ::core::result::Result<Self, ::better_any::__Box<dyn ::better_any::Tid<#lifetime> + #lifetime>> {
+                #(
+                    let value = match <_ as ::better_any::TidExt>::downcast_box::<#types>(value) {
+                        ::core::result::Result::Ok(value) => {
+                            return ::core::result::Result::Ok(#ident::#names(*value))
+                        }
+                        ::core::result::Result::Err(value) => value,
+                    };
+                )*
+                ::core::result::Result::Err(value)
+            }
+        }
+    }
+    .into()
+}
+
 fn create_impl(
     generics: Generics,
     type_: Box<Type>,
     hlq: Option<Ident>,
 ) -> proc_macro2::TokenStream {
-    let hlq = hlq.map(|it| quote!(#it::)).unwrap_or(quote!());
+    let hlq = hlq.map(|it| quote!(#it::)).unwrap_or_default();
 
     // no generics
     if generics.lt_token.is_none() {
@@ -102,13 +460,18 @@ fn create_impl(
             }
         };
 
-        return tokens.into();
+        return tokens;
     }
 
-    let lifetime_count = generics.lifetimes().count();
-    if lifetime_count > 1 {
-        unimplemented!("currently only single lifetime is supported")
+    if let Some(extra) = generics.lifetimes().nth(1) {
+        return syn::Error::new_spanned(
+            &extra.lifetime,
+            "only single lifetime parameter is supported, \
+             use `#[tid(unify_lifetimes)]` to implement `Tid` for the case when all lifetimes are the same",
+        )
+        .to_compile_error();
     }
+    let lifetime_count = generics.lifetimes().count();
     let lifetime = generics
         .lifetimes()
         .next()
@@ -123,6 +486,10 @@ fn create_impl(
         .const_params()
         .map(|it| &it.ident)
         .collect::<Vec<_>>();
+    let const_params = generics
+        .const_params()
+        .map(|ConstParam { ident, ty, .. }| quote! {const #ident: #ty})
+        .collect::<Vec<_>>();
     // let const_param_names2 = generics.const_params().map(|it| &it.ident);
 
     // let where_clause = generics.where_clause.as_ref();
@@ -134,33 +501,34 @@ fn create_impl(
         for generic in generic_params.iter() {
             if let GenericParam::Type(TypeParam { ident, bounds, .. }) = generic {
                 // add Tid bound
-                if bounds.iter().any(|it| is_static(it)) {
+                if bounds.iter().any(is_static) {
                     substitute_types.push(ident.to_token_stream())
                 } else {
                     substitute_types.push(quote! {#ident::Static});
-                    where_with_bounds
-                        .predicates
-                        .push(syn::parse2(quote! {#ident: #hlq TidAble<#lifetime>}).unwrap());
+                    // spanned so that unsatisfied bound is reported at the type parameter
+                    where_with_bounds.predicates.push(
+                        syn::parse2(
+                            quote_spanned! {ident.span()=> #ident: #hlq TidAble<#lifetime>},
+                        )
+                        .unwrap(),
+                    );
                 }
             }
         }
     }
     // remove defaults
-    generics_with_bounds.params.iter_mut().for_each(|param| {
-        if let GenericParam::Type(TypeParam { default, .. }) = param {
-            *default = None
-        }
-    });
+    generics_with_bounds
+        .params
+        .iter_mut()
+        .for_each(|param| match param {
+            GenericParam::Type(TypeParam { default, .. }) => *default = None,
+            GenericParam::Const(ConstParam { default, .. }) => *default = None,
+            GenericParam::Lifetime(_) => {}
+        });
     let where_with_bounds = generics_with_bounds.where_clause.as_ref();
     let type_params_wo_defaults = &generics_with_bounds.params;
 
-    let name = type_
-        .to_token_stream()
-        .to_string()
-        .chars()
-        .filter(char::is_ascii_alphanumeric)
-        .collect::<String>();
-    let temp_struct_ident = quote::format_ident!("__{}_should_never_exist", name);
+    let temp_struct_ident = quote::format_ident!("__TypeIdGenerator");
     let tokens = if lifetime_count == 1 {
         quote! {
             unsafe impl<#type_params_wo_defaults> #hlq TidAble<#lifetime> for #type_
@@ -179,16 +547,18 @@ fn create_impl(
     };
 
     // need to use separate struct becaus if we use original struct,
-    // we have to forward all bounds
+    // we have to forward all bounds.
+    // anonymous const makes it unique per type and keeps it out of user's namespace
     let tokens = quote! {
-        #tokens
-        #[allow(warnings)]
-        #[doc(hidden)]
-        pub struct #temp_struct_ident<#(#type_param_names:?Sized,)* #(#const_param_names,)*>
-            (#(core::marker::PhantomData<#type_param_names>,)* #(#const_param_names,)*);
+        const _: () = {
+            #tokens
+            #[allow(warnings)]
+            pub struct #temp_struct_ident<#(#type_param_names:?Sized,)* #(#const_params,)*>
+                (#(core::marker::PhantomData<#type_param_names>,)*);
+        };
     };
 
-    return tokens;
+    tokens
 }
 
 #[proc_macro_attribute]